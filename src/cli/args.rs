@@ -1,20 +1,65 @@
-use crate::output::printer::{DisplayMode, print_error, print_info};
+use super::query::{Token, parse_query};
+use crate::{
+    matcher::{Engine, Folding, Query, Scope},
+    output::printer::{ColumnUnit, DisplayMode, PrintOptions, print_error, print_info},
+    schemas::options::{BinaryMode, CsvColumn, StringsOptions},
+};
+use encoding_rs::Encoding;
 use std::{env, num::NonZeroUsize, process::exit};
 
 const DEFAULT_PATH: &str = ".";
 
+/// Record separator used by `--paragraph`: one or more blank lines.
+const PARAGRAPH_SEPARATOR: &str = r"\r?\n(?:[ \t]*\r?\n)+";
+
+/// Levels of nested archives opened by `--search-archives` unless `--archive-depth` says
+/// otherwise.
+const DEFAULT_ARCHIVE_DEPTH: usize = 3;
+
+/// Shortest printable run `--strings` keeps, like `strings` does.
+const DEFAULT_STRINGS_MIN_LEN: usize = 4;
+
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub struct Args {
-    pub pattern: String,
+    pub patterns: Vec<String>,
+    pub query: Query,
+    pub scope: Scope,
+    pub engine: Engine,
+    pub folding: Option<Folding>,
     pub path: String,
     pub threads: usize,
     pub type_style: DisplayMode,
+    pub replace: Option<String>,
+    pub confirm: bool,
+    pub edit_results: bool,
+    pub encoding: Option<&'static Encoding>,
+    pub binary: BinaryMode,
+    pub crlf: bool,
+    pub near: Option<usize>,
+    pub record_separator: Option<String>,
+    pub null_data: bool,
+    pub search_zip: bool,
+    pub archive_depth: usize,
+    pub pre: Option<String>,
+    pub pre_globs: Vec<String>,
+    pub documents: bool,
+    pub notebook_outputs: bool,
+    pub strings: Option<StringsOptions>,
+    pub path_query: Option<String>,
+    pub path_keys: bool,
+    pub csv_columns: Vec<CsvColumn>,
+    pub sqlite: bool,
+    pub print_options: PrintOptions,
 }
 
 fn usage() {
     let program = env::args().next().unwrap_or_else(|| "sik".to_string());
     println!("Usage: {} [OPTS] <PATTERN> [PATH]", program);
+    println!(
+        "       {} [OPTS] -e <PATTERN> [--and|--or|--not -e <PATTERN>...] [PATH]",
+        program
+    );
     println!("\nArgs:");
     println!("  <PATTERN>             Pattern to be searched for");
     println!("  [PATH]                Path to be searched with the pattern");
@@ -23,6 +68,61 @@ fn usage() {
     println!(
         "  -t, --threads <NUM>   Number of threads to be used, default is number of logical processors * 2",
     );
+    println!(
+        "  -E, --encoding <LABEL> Read files as LABEL (utf-16le, latin1...), default sniffs the BOM"
+    );
+    println!("  -e, --regexp <PATTERN> Add a pattern to a boolean expression (replaces <PATTERN>)");
+    println!("  --and, --or, --not    Combine the -e patterns, '(' and ')' group them");
+    println!(
+        "  --files               Evaluate the expression over the whole file instead of each line"
+    );
+    println!("  --near <N>            Report where all the -e patterns occur within N lines");
+    println!("  -P, --perl-regexp     Use a backtracking engine (lookaround, backreferences)");
+    println!("  --fuzzy <N>           Match the pattern literally, allowing up to N edits (typos)");
+    println!("  --normalize           NFC-normalize the pattern and the text before matching");
+    println!("  --ignore-diacritics   Ignore accents and other diacritics when matching");
+    println!("  -z, --search-zip      Search inside gzip, bzip2, xz and zstd compressed files");
+    println!("  --search-archives     Search the members of zip, jar, tar and tar.gz archives");
+    println!("  --archive-depth <N>   Levels of nested archives to open, default is 3");
+    println!("  --pre <COMMAND>       Search the output of 'COMMAND <file>' instead of each file");
+    println!("  --pre-glob <GLOB>     Only run --pre on the files matching GLOB (repeatable)");
+    println!(
+        "  --docs                Search the text of .docx, .odt, .xlsx, .epub and .pdf documents"
+    );
+    println!("  --notebook-outputs    With --docs, search the text outputs of notebook cells too");
+    println!(
+        "  --strings             Search the printable strings of binary files, with their offsets"
+    );
+    println!("  --strings-min <N>     Shortest string --strings keeps, default is 4 bytes");
+    println!("  --strings-utf16       Make --strings look for UTF-16LE text too");
+    println!("  --hex                 <PATTERN> is a byte sequence in hex, '??' matching any byte");
+    println!(
+        "  --path-query <QUERY>  Search the values at QUERY ('$.deps.*.version') in JSON, YAML, TOML"
+    );
+    println!("  --path-keys           Make --path-query search the keys it selects instead");
+    println!(
+        "  --csv-column <COLUMN> Only search COLUMN (header name or 1-based index) of CSV/TSV files"
+    );
+    println!(
+        "  --sqlite              Search the text columns of .sqlite and .db files, opened read-only"
+    );
+    println!("  --binary              Search binary files, reporting only that they match");
+    println!("  -a, --text            Search binary files as if they were text");
+    println!("  --crlf                Strip the '\\r' of CRLF line endings (the default)");
+    println!("  --no-crlf             Keep the '\\r' of CRLF line endings in the searched lines");
+    println!("  --paragraph           Search blank-line separated paragraphs instead of lines");
+    println!("  --record-separator <REGEX> Search records delimited by REGEX instead of lines");
+    println!("  --null-data           Input records end with NUL instead of a newline");
+    println!("  -l, --files-with-matches Only print the paths of the matching files");
+    println!("  -0, --null            End printed file paths with NUL instead of a newline");
+    println!("  --column              Print the column of the first match in each line");
+    println!("  --column-unit <UNIT>  Count --column in 'chars' (default) or 'graphemes'");
+    println!("  -b, --byte-offset     Print the byte offset of the first match in the file");
+    println!(
+        "  -r, --replace <TEXT>  Replace every match with TEXT ($1, $name expand capture groups)"
+    );
+    println!("  -c, --confirm         Ask before applying each replacement (y/n/a/q)");
+    println!("  --edit-results        Open the matching lines in $EDITOR and write the edits back");
     println!("  -h, --help            Prints this message\n");
 
     print_info(&format!("Version: {}", VERSION));
@@ -33,9 +133,41 @@ fn usage() {
 impl Args {
     pub fn parse() -> Self {
         let mut args_iter = env::args().skip(1);
-        let mut pattern = String::new();
-        let mut path = String::new();
+        let mut positionals: Vec<String> = Vec::new();
+        let mut patterns: Vec<String> = Vec::new();
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut scope = Scope::Lines;
+        let mut near = None;
+        let mut record_separator = None;
+        let mut null_data = false;
+        let mut search_zip = false;
+        let mut search_archives = false;
+        let mut archive_depth = DEFAULT_ARCHIVE_DEPTH;
+        let mut pre = None;
+        let mut pre_globs = Vec::new();
+        let mut documents = false;
+        let mut notebook_outputs = false;
+        let mut strings = false;
+        let mut hex = false;
+        let mut path_query = None;
+        let mut path_keys = false;
+        let mut csv_columns = Vec::new();
+        let mut sqlite = false;
+        let mut strings_options = StringsOptions {
+            min_len: DEFAULT_STRINGS_MIN_LEN,
+            utf16: false,
+        };
         let mut type_style = DisplayMode::Primary;
+        let mut replace = None;
+        let mut confirm = false;
+        let mut edit_results = false;
+        let mut encoding = None;
+        let mut binary = BinaryMode::Skip;
+        let mut crlf = true;
+        let mut engine = Engine::Fast;
+        let mut folding = None;
+        let mut print_options = PrintOptions::default();
+        let mut column_unit = ColumnUnit::Chars;
 
         let mut threads = std::thread::available_parallelism()
             .unwrap_or(NonZeroUsize::new(2).unwrap())
@@ -81,6 +213,327 @@ impl Args {
                     };
                 }
 
+                "-E" | "--encoding" => {
+                    let label = match args_iter.next() {
+                        Some(val) => val,
+                        None => {
+                            print_error("--encoding is expected to receive an encoding label");
+                            usage();
+                            exit(1);
+                        }
+                    };
+                    encoding = match Encoding::for_label(label.as_bytes()) {
+                        Some(enc) => Some(enc),
+                        None => {
+                            print_error(&format!("Unknown encoding: '{}'.", label));
+                            usage();
+                            exit(1);
+                        }
+                    };
+                }
+
+                "-e" | "--regexp" => match args_iter.next() {
+                    Some(val) => {
+                        tokens.push(Token::Term(patterns.len()));
+                        patterns.push(val);
+                    }
+                    None => {
+                        print_error("-e is expected to receive a pattern");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "--and" => tokens.push(Token::And),
+                "--or" => tokens.push(Token::Or),
+                "--not" => tokens.push(Token::Not),
+                "(" => tokens.push(Token::Open),
+                ")" => tokens.push(Token::Close),
+
+                "--files" => {
+                    scope = Scope::Files;
+                }
+
+                "--near" => {
+                    let num_str = match args_iter.next() {
+                        Some(val) => val,
+                        None => {
+                            print_error("--near is expected to receive a number of lines");
+                            usage();
+                            exit(1);
+                        }
+                    };
+                    near = match num_str.parse() {
+                        Ok(num) => Some(num),
+                        Err(_) => {
+                            print_error(&format!(
+                                "Invalid number of lines: '{}'. Must be a number.",
+                                num_str
+                            ));
+                            usage();
+                            exit(1);
+                        }
+                    };
+                }
+
+                "-P" | "--perl-regexp" => {
+                    if let Engine::Fuzzy(_) = engine {
+                        print_error("-P cannot be used together with --fuzzy.");
+                        usage();
+                        exit(1);
+                    }
+                    engine = Engine::Backtracking;
+                }
+
+                "--fuzzy" => {
+                    let num_str = match args_iter.next() {
+                        Some(val) => val,
+                        None => {
+                            print_error("--fuzzy is expected to receive a number of edits");
+                            usage();
+                            exit(1);
+                        }
+                    };
+                    if engine == Engine::Backtracking {
+                        print_error("--fuzzy cannot be used together with -P.");
+                        usage();
+                        exit(1);
+                    }
+                    engine = match num_str.parse() {
+                        Ok(num) => Engine::Fuzzy(num),
+                        Err(_) => {
+                            print_error(&format!(
+                                "Invalid number of edits: '{}'. Must be a number.",
+                                num_str
+                            ));
+                            usage();
+                            exit(1);
+                        }
+                    };
+                }
+
+                "--normalize" => {
+                    // --ignore-diacritics already normalizes
+                    if folding.is_none() {
+                        folding = Some(Folding::Nfc);
+                    }
+                }
+
+                "--ignore-diacritics" => {
+                    folding = Some(Folding::StripDiacritics);
+                }
+
+                "-z" | "--search-zip" => {
+                    search_zip = true;
+                }
+
+                "--search-archives" => {
+                    search_archives = true;
+                }
+
+                "--archive-depth" => {
+                    let num_str = match args_iter.next() {
+                        Some(val) => val,
+                        None => {
+                            print_error("--archive-depth is expected to receive a number");
+                            usage();
+                            exit(1);
+                        }
+                    };
+                    archive_depth = match num_str.parse() {
+                        Ok(num) if num > 0 => num,
+                        _ => {
+                            print_error(&format!(
+                                "Invalid archive depth: '{}'. Must be a positive number.",
+                                num_str
+                            ));
+                            usage();
+                            exit(1);
+                        }
+                    };
+                }
+
+                "--pre" => match args_iter.next() {
+                    Some(val) => pre = Some(val),
+                    None => {
+                        print_error("--pre is expected to receive a command");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "--pre-glob" => match args_iter.next() {
+                    Some(val) => pre_globs.push(val),
+                    None => {
+                        print_error("--pre-glob is expected to receive a glob");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "--docs" => {
+                    documents = true;
+                }
+
+                "--notebook-outputs" => {
+                    notebook_outputs = true;
+                }
+
+                "--strings" => {
+                    strings = true;
+                }
+
+                "--strings-min" => {
+                    let num_str = match args_iter.next() {
+                        Some(val) => val,
+                        None => {
+                            print_error("--strings-min is expected to receive a number");
+                            usage();
+                            exit(1);
+                        }
+                    };
+                    strings_options.min_len = match num_str.parse() {
+                        Ok(num) if num > 0 => num,
+                        _ => {
+                            print_error(&format!(
+                                "Invalid string length: '{}'. Must be a positive number.",
+                                num_str
+                            ));
+                            usage();
+                            exit(1);
+                        }
+                    };
+                }
+
+                "--strings-utf16" => {
+                    strings_options.utf16 = true;
+                }
+
+                "--hex" => {
+                    hex = true;
+                }
+
+                "--path-query" => match args_iter.next() {
+                    Some(val) => path_query = Some(val),
+                    None => {
+                        print_error("--path-query is expected to receive a query");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "--path-keys" => {
+                    path_keys = true;
+                }
+
+                "--csv-column" => match args_iter.next() {
+                    // `=2020` names a column whose header is a number
+                    Some(val) if val.starts_with('=') => {
+                        csv_columns.push(CsvColumn::Name(val[1..].to_string()))
+                    }
+                    Some(val) => csv_columns.push(match val.parse::<usize>() {
+                        Ok(0) => {
+                            print_error("--csv-column indexes start at 1.");
+                            usage();
+                            exit(1);
+                        }
+                        Ok(index) => CsvColumn::Index(index - 1),
+                        Err(_) => CsvColumn::Name(val),
+                    }),
+                    None => {
+                        print_error("--csv-column is expected to receive a column name or index");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "--sqlite" => {
+                    if !cfg!(feature = "sqlite") {
+                        print_error("--sqlite needs sik to be built with the sqlite feature.");
+                        usage();
+                        exit(1);
+                    }
+                    sqlite = true;
+                }
+
+                "--binary" => {
+                    binary = BinaryMode::Search;
+                }
+
+                "-a" | "--text" => {
+                    binary = BinaryMode::Text;
+                }
+
+                "--crlf" => {
+                    crlf = true;
+                }
+
+                "--no-crlf" => {
+                    crlf = false;
+                }
+
+                "--paragraph" => {
+                    record_separator = Some(PARAGRAPH_SEPARATOR.to_string());
+                }
+
+                "--record-separator" => match args_iter.next() {
+                    Some(val) => record_separator = Some(val),
+                    None => {
+                        print_error("--record-separator is expected to receive a regex");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "--null-data" => {
+                    null_data = true;
+                }
+
+                "-l" | "--files-with-matches" => {
+                    print_options.files_with_matches = true;
+                }
+
+                "-0" | "--null" => {
+                    print_options.null = true;
+                }
+
+                "--column" => {
+                    print_options.column = Some(column_unit);
+                }
+
+                "--column-unit" => {
+                    column_unit = match args_iter.next().as_deref() {
+                        Some("chars") => ColumnUnit::Chars,
+                        Some("graphemes") => ColumnUnit::Graphemes,
+                        _ => {
+                            print_error("--column-unit is expected to be 'chars' or 'graphemes'");
+                            usage();
+                            exit(1);
+                        }
+                    };
+                }
+
+                "-b" | "--byte-offset" => {
+                    print_options.byte_offset = true;
+                }
+
+                "-r" | "--replace" => match args_iter.next() {
+                    Some(val) => replace = Some(val),
+                    None => {
+                        print_error("--replace is expected to receive the replacement text");
+                        usage();
+                        exit(1);
+                    }
+                },
+
+                "-c" | "--confirm" => {
+                    confirm = true;
+                }
+
+                "--edit-results" => {
+                    edit_results = true;
+                }
+
                 // unknown opt
                 s if s.starts_with('-') => {
                     print_error(&format!("Unknown option: {}", s));
@@ -88,35 +541,236 @@ impl Args {
                     exit(1);
                 }
 
-                _ => {
-                    if pattern.is_empty() {
-                        pattern = arg;
-                    } else if path.is_empty() {
-                        path = arg;
-                    } else {
-                        print_error(&format!("Unexpected argument: {}", arg));
-                        usage();
-                        exit(1);
-                    }
+                _ => positionals.push(arg),
+            }
+        }
+
+        // with -e the patterns are already known and the only positional is the path
+        let mut positionals = positionals.into_iter();
+        if patterns.is_empty() {
+            match positionals.next() {
+                Some(pattern) => {
+                    tokens.push(Token::Term(0));
+                    patterns.push(pattern);
                 }
+                None => {
+                    print_error("Required argument <PATTERN> is missing.");
+                    usage();
+                    exit(1);
+                }
+            }
+        }
+
+        let path = positionals
+            .next()
+            .unwrap_or_else(|| DEFAULT_PATH.to_string());
+
+        if let Some(arg) = positionals.next() {
+            print_error(&format!("Unexpected argument: {}", arg));
+            usage();
+            exit(1);
+        }
+
+        if hex {
+            if patterns.len() > 1 || near.is_some() {
+                print_error("--hex takes a single pattern, without -e expressions or --near.");
+                usage();
+                exit(1);
+            }
+            if tokens.iter().any(|t| !matches!(t, Token::Term(_))) || scope == Scope::Files {
+                print_error("--hex cannot be combined with --and, --or, --not or --files.");
+                usage();
+                exit(1);
+            }
+            if engine != Engine::Fast || folding.is_some() {
+                print_error("--hex cannot be combined with -P, --fuzzy or the folding options.");
+                usage();
+                exit(1);
             }
+            if record_separator.is_some() || null_data {
+                print_error("--hex cannot be used together with records.");
+                usage();
+                exit(1);
+            }
+            if replace.is_some() || edit_results {
+                print_error("--hex cannot be used together with --replace or --edit-results.");
+                usage();
+                exit(1);
+            }
+            // the offset of each match is always shown, the hexdump has no columns
+            if print_options.column.is_some() || print_options.byte_offset {
+                print_error("--hex cannot be used together with --column or -b.");
+                usage();
+                exit(1);
+            }
+            engine = Engine::Hex;
         }
 
-        if pattern.is_empty() {
-            print_error("Required argument <PATTERN> is missing.");
+        // each string is labelled with its own offset, a record would join several of them
+        if strings && (record_separator.is_some() || null_data) {
+            print_error("--strings cannot be used together with records.");
             usage();
             exit(1);
         }
 
-        if path.is_empty() {
-            path = DEFAULT_PATH.to_string();
+        if near.is_some() {
+            if patterns.len() < 2 {
+                print_error("--near needs at least two patterns given with -e.");
+                usage();
+                exit(1);
+            }
+            if tokens.iter().any(|t| !matches!(t, Token::Term(_))) || scope == Scope::Files {
+                print_error("--near cannot be combined with --and, --or, --not or --files.");
+                usage();
+                exit(1);
+            }
+            if replace.is_some() || edit_results {
+                print_error("--near cannot be used together with --replace or --edit-results.");
+                usage();
+                exit(1);
+            }
+        }
+
+        if print_options.files_with_matches && (replace.is_some() || edit_results) {
+            print_error("-l cannot be used together with --replace or --edit-results.");
+            usage();
+            exit(1);
+        }
+
+        if !pre_globs.is_empty() && pre.is_none() {
+            print_error("--pre-glob cannot be used without --pre.");
+            usage();
+            exit(1);
+        }
+
+        if notebook_outputs && !documents {
+            print_error("--notebook-outputs cannot be used without --docs.");
+            usage();
+            exit(1);
+        }
+
+        if path_keys && path_query.is_none() {
+            print_error("--path-keys cannot be used without --path-query.");
+            usage();
+            exit(1);
+        }
+
+        if hex && path_query.is_some() {
+            print_error("--hex cannot be used together with --path-query.");
+            usage();
+            exit(1);
+        }
+
+        if !csv_columns.is_empty() {
+            if hex || path_query.is_some() {
+                print_error("--csv-column cannot be combined with --hex or --path-query.");
+                usage();
+                exit(1);
+            }
+            if near.is_some() || record_separator.is_some() || null_data {
+                print_error("--csv-column cannot be used together with --near or records.");
+                usage();
+                exit(1);
+            }
+            if replace.is_some() || edit_results {
+                print_error(
+                    "--csv-column cannot be used together with --replace or --edit-results.",
+                );
+                usage();
+                exit(1);
+            }
+        }
+
+        if sqlite {
+            if hex || scope == Scope::Files {
+                print_error("--sqlite cannot be combined with --hex or --files.");
+                usage();
+                exit(1);
+            }
+            if replace.is_some() || edit_results {
+                print_error("--sqlite cannot be used together with --replace or --edit-results.");
+                usage();
+                exit(1);
+            }
+        }
+
+        if null_data && record_separator.is_some() {
+            print_error(
+                "--null-data cannot be used together with --paragraph or --record-separator.",
+            );
+            usage();
+            exit(1);
+        }
+
+        if record_separator.is_some() || null_data {
+            if near.is_some() {
+                print_error("--near cannot be used together with records.");
+                usage();
+                exit(1);
+            }
+            if replace.is_some() || edit_results {
+                print_error("--replace and --edit-results cannot be used together with records.");
+                usage();
+                exit(1);
+            }
+        }
+
+        let query = match parse_query(&tokens) {
+            Ok(query) => query,
+            Err(e) => {
+                print_error(&format!("Invalid expression: {}.", e));
+                usage();
+                exit(1);
+            }
+        };
+
+        // the unit may come before or after --column
+        if print_options.column.is_some() {
+            print_options.column = Some(column_unit);
+        }
+
+        if confirm && replace.is_none() {
+            print_error("--confirm can only be used together with --replace.");
+            usage();
+            exit(1);
+        }
+
+        if edit_results && replace.is_some() {
+            print_error("--edit-results cannot be used together with --replace.");
+            usage();
+            exit(1);
         }
 
         Self {
-            pattern,
+            patterns,
+            query,
+            scope,
+            engine,
+            folding,
             path,
             threads,
             type_style,
+            replace,
+            confirm,
+            edit_results,
+            encoding,
+            binary,
+            crlf,
+            near,
+            record_separator,
+            null_data,
+            search_zip,
+            archive_depth: if search_archives { archive_depth } else { 0 },
+            pre,
+            pre_globs,
+            documents,
+            notebook_outputs,
+            strings: strings.then_some(strings_options),
+            path_query,
+            path_keys,
+            csv_columns,
+            sqlite,
+            print_options,
         }
     }
 }
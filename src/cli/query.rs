@@ -0,0 +1,178 @@
+use crate::matcher::Query;
+use std::{
+    fmt::{self, Display},
+    iter::Peekable,
+    slice::Iter,
+};
+
+/// Pieces of a boolean expression as they appear on the command line.
+#[derive(Debug)]
+pub enum Token {
+    /// `-e PATTERN`, holding the index of the pattern.
+    Term(usize),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Term(_) => write!(f, "-e"),
+            Token::And => write!(f, "--and"),
+            Token::Or => write!(f, "--or"),
+            Token::Not => write!(f, "--not"),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+        }
+    }
+}
+
+/// Parses the tokens into a [`Query`], with the same precedence as `git grep`: `--not` binds
+/// tighter than `--and`, which binds tighter than `--or`. Terms written next to each other
+/// without an operator are joined with `--or`, except before `--not`, which implies `--and`.
+pub fn parse_query(tokens: &[Token]) -> Result<Query, String> {
+    let mut tokens = tokens.iter().peekable();
+    let query = parse_or(&mut tokens)?;
+
+    match tokens.next() {
+        None => Ok(query),
+        Some(Token::Close) => Err("unbalanced ')' in the expression".to_string()),
+        Some(t) => Err(format!("unexpected {} in the expression", t)),
+    }
+}
+
+fn parse_or(tokens: &mut Peekable<Iter<Token>>) -> Result<Query, String> {
+    let mut query = parse_and(tokens)?;
+
+    loop {
+        match tokens.peek() {
+            Some(Token::Or) => {
+                tokens.next();
+            }
+            // implicit --or
+            Some(Token::Term(_) | Token::Open) => {}
+            _ => return Ok(query),
+        }
+        query = Query::Or(Box::new(query), Box::new(parse_and(tokens)?));
+    }
+}
+
+fn parse_and(tokens: &mut Peekable<Iter<Token>>) -> Result<Query, String> {
+    let mut query = parse_not(tokens)?;
+
+    loop {
+        match tokens.peek() {
+            Some(Token::And) => {
+                tokens.next();
+            }
+            // `-e A --not -e B` reads as "A but not B"
+            Some(Token::Not) => {}
+            _ => return Ok(query),
+        }
+        query = Query::And(Box::new(query), Box::new(parse_not(tokens)?));
+    }
+}
+
+fn parse_not(tokens: &mut Peekable<Iter<Token>>) -> Result<Query, String> {
+    match tokens.next() {
+        Some(Token::Not) => Ok(Query::Not(Box::new(parse_not(tokens)?))),
+        Some(Token::Term(i)) => Ok(Query::Term(*i)),
+        Some(Token::Open) => {
+            let query = parse_or(tokens)?;
+            match tokens.next() {
+                Some(Token::Close) => Ok(query),
+                _ => Err("missing ')' in the expression".to_string()),
+            }
+        }
+        Some(t) => Err(format!("expected a pattern (-e) but found {}", t)),
+        None => Err("the expression ends where a pattern (-e) was expected".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a compact spelling of the command line: digits are `-e` terms, `&`, `|` and `!`
+    /// the operators, and the result is shown fully parenthesized.
+    fn parse(spec: &str) -> Result<String, String> {
+        let tokens: Vec<Token> = spec
+            .split_whitespace()
+            .map(|t| match t {
+                "&" => Token::And,
+                "|" => Token::Or,
+                "!" => Token::Not,
+                "(" => Token::Open,
+                ")" => Token::Close,
+                term => Token::Term(term.parse().unwrap()),
+            })
+            .collect();
+        parse_query(&tokens).map(|query| show(&query))
+    }
+
+    fn show(query: &Query) -> String {
+        match query {
+            Query::Term(i) => i.to_string(),
+            Query::And(a, b) => format!("({} & {})", show(a), show(b)),
+            Query::Or(a, b) => format!("({} | {})", show(a), show(b)),
+            Query::Not(a) => format!("!{}", show(a)),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(parse("0 | 1 & 2").unwrap(), "(0 | (1 & 2))");
+        assert_eq!(parse("0 & 1 | 2").unwrap(), "((0 & 1) | 2)");
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(parse("! 0 & 1").unwrap(), "(!0 & 1)");
+        assert_eq!(parse("! ! 0").unwrap(), "!!0");
+    }
+
+    #[test]
+    fn adjacent_terms_are_joined_with_or() {
+        assert_eq!(parse("0 1 2").unwrap(), "((0 | 1) | 2)");
+        assert_eq!(parse("0 ( 1 & 2 )").unwrap(), "(0 | (1 & 2))");
+    }
+
+    #[test]
+    fn not_after_a_term_implies_and() {
+        assert_eq!(parse("0 ! 1").unwrap(), "(0 & !1)");
+        assert_eq!(parse("0 1 ! 2").unwrap(), "(0 | (1 & !2))");
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_eq!(parse("( 0 | 1 ) & 2").unwrap(), "((0 | 1) & 2)");
+        assert_eq!(parse("! ( 0 | 1 )").unwrap(), "!(0 | 1)");
+    }
+
+    #[test]
+    fn reports_malformed_expressions() {
+        assert_eq!(
+            parse("0 )").unwrap_err(),
+            "unbalanced ')' in the expression"
+        );
+        assert_eq!(
+            parse("( 0 & 1").unwrap_err(),
+            "missing ')' in the expression"
+        );
+        assert_eq!(
+            parse("0 & | 1").unwrap_err(),
+            "expected a pattern (-e) but found --or"
+        );
+        assert_eq!(
+            parse("0 &").unwrap_err(),
+            "the expression ends where a pattern (-e) was expected"
+        );
+        assert_eq!(
+            parse("| 0").unwrap_err(),
+            "expected a pattern (-e) but found --or"
+        );
+    }
+}
@@ -1,29 +1,59 @@
-// FIXME: REFACTOR ALL THIS SHIT, HORRIBLE, NEEDS TO BE FLEXIBLE, PROBABLY CREATE A FUNCTION AND
-// PASS AN ENUM OF COLORS TO PAINT IT
-
 const RED: &str = "\x1b[31m";
 const GREEN: &str = "\x1b[32m";
 const BLUE: &str = "\x1b[34m";
 const YELLOW: &str = "\x1b[1;33m";
 const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[1;36m";
 const RESET: &str = "\x1b[0m";
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+    Magenta,
+    Cyan,
+}
+
+/// Colors used to highlight matches, the pattern with index `i` (`-e`) gets
+/// `MATCH_COLORS[i % MATCH_COLORS.len()]`. The first one is the usual yellow.
+pub const MATCH_COLORS: [Color; 5] = [
+    Color::Yellow,
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Red,
+];
+
+pub fn paint(text: &str, color: Color) -> String {
+    let code = match color {
+        Color::Red => RED,
+        Color::Green => GREEN,
+        Color::Blue => BLUE,
+        Color::Yellow => YELLOW,
+        Color::Magenta => MAGENTA,
+        Color::Cyan => CYAN,
+    };
+    format!("{}{}{}", code, text, RESET)
+}
+
 pub fn paint_red(text: &str) -> String {
-    format!("{}{}{}", RED, text, RESET)
+    paint(text, Color::Red)
 }
 
 pub fn paint_green(text: &str) -> String {
-    format!("{}{}{}", GREEN, text, RESET)
+    paint(text, Color::Green)
 }
 
 pub fn paint_blue(text: &str) -> String {
-    format!("{}{}{}", BLUE, text, RESET)
+    paint(text, Color::Blue)
 }
 
 pub fn paint_yellow(text: &str) -> String {
-    format!("{}{}{}", YELLOW, text, RESET)
+    paint(text, Color::Yellow)
 }
 
 pub fn paint_magenta(text: &str) -> String {
-    format!("{}{}{}", MAGENTA, text, RESET)
+    paint(text, Color::Magenta)
 }
@@ -0,0 +1,5 @@
+mod replace;
+mod results;
+mod source;
+pub use replace::{ReplaceSummary, apply_replacements};
+pub use results::{EditSummary, edit_results};
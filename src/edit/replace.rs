@@ -0,0 +1,190 @@
+use super::source::Source;
+use crate::{
+    colors::painter::{paint_blue, paint_green, paint_magenta},
+    errors::custom_errors::AppError,
+    matcher::Matcher,
+    output::printer::{center_ansi, highlight, print_warning},
+    schemas::files::{FileResult, MatchRange, MatchResult, SearchResult},
+};
+use encoding_rs::Encoding;
+use std::io::{self, BufRead, Write};
+
+/// Number of lines shown above and below a match while asking for confirmation.
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, Copy)]
+enum Decision {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+#[derive(Default)]
+pub struct ReplaceSummary {
+    pub replacements: usize,
+    pub files: usize,
+}
+
+/// Rewrites every match found in `results` with `replacement`, expanding capture group
+/// references (`$1`, `${name}`) the same way `Regex::replace` does.
+///
+/// When `confirm` is set, each match is shown with some surrounding lines and the user is
+/// asked `y/n/a/q` before it is applied, like vim's `:s///c`. Only the accepted edits are
+/// written back. A file whose lines no longer match what the workers read is skipped.
+pub fn apply_replacements(
+    results: &[FileResult],
+    patterns: &[Matcher],
+    replacement: &str,
+    confirm: bool,
+    encoding: Option<&'static Encoding>,
+) -> Result<ReplaceSummary, AppError> {
+    let mut summary = ReplaceSummary::default();
+    let mut ask = confirm;
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    'files: for file in results.iter().filter(|f| f.is_editable()) {
+        let path = file.path.to_str().ok_or(AppError::InvalidPath)?;
+
+        let mut source = match Source::read(&file.path, encoding) {
+            Ok(source) => source,
+            Err(e) => {
+                print_warning(&format!("skipping {}: {}", path, e));
+                continue;
+            }
+        };
+        let lines = &mut source.lines;
+        let mut replaced = 0;
+        let mut quit = false;
+
+        for r in &file.results {
+            let idx = r.line - 1;
+            let original = format!("{}{}", r.line_content, r.terminator);
+            if lines.get(idx) != Some(&original) {
+                print_warning(&format!(
+                    "skipping {}:{}, the line changed since it was searched",
+                    path, r.line
+                ));
+                continue;
+            }
+
+            let mut accepted = Vec::new();
+
+            for m in &r.matches {
+                if ask {
+                    print_context(path, lines, r, m.match_range);
+                    match prompt(&mut input, replacement)? {
+                        Decision::Yes => accepted.push(m),
+                        Decision::No => {}
+                        Decision::All => {
+                            accepted.push(m);
+                            ask = false;
+                        }
+                        Decision::Quit => {
+                            quit = true;
+                            break;
+                        }
+                    }
+                } else {
+                    accepted.push(m);
+                }
+            }
+
+            if !accepted.is_empty() {
+                let replaced_line =
+                    replace_matches(&r.line_content, &accepted, patterns, replacement);
+                lines[idx] = format!("{}{}", replaced_line, r.terminator);
+                replaced += accepted.len();
+            }
+
+            if quit {
+                break;
+            }
+        }
+
+        if replaced > 0 {
+            match source.write(&file.path) {
+                Ok(()) => {
+                    summary.replacements += replaced;
+                    summary.files += 1;
+                }
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    print_warning(&format!("skipping {}: {}", path, e));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        if quit {
+            break 'files;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn replace_matches(
+    line: &str,
+    matches: &[&MatchResult],
+    patterns: &[Matcher],
+    replacement: &str,
+) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last_end = 0;
+
+    for m in matches {
+        let (start, end) = m.match_range;
+        out.push_str(&line[last_end..start]);
+
+        patterns[m.term].expand(line, m.match_range, replacement, &mut out);
+
+        last_end = end;
+    }
+
+    out.push_str(&line[last_end..]);
+    out
+}
+
+fn print_context(path: &str, lines: &[String], r: &SearchResult, range: MatchRange) {
+    let idx = r.line - 1;
+    let first = idx.saturating_sub(CONTEXT_LINES);
+    let last = (idx + CONTEXT_LINES).min(lines.len() - 1);
+
+    println!();
+    println!("{}", paint_blue(path));
+
+    for (i, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let no = paint_green(&(i + 1).to_string());
+        let text = if i == idx {
+            highlight(&r.line_content, &[range])
+        } else {
+            line.trim_end_matches(['\r', '\n']).to_string()
+        };
+        println!("[{}]: {}", center_ansi(&no, 4), text);
+    }
+}
+
+fn prompt(input: &mut impl BufRead, replacement: &str) -> Result<Decision, AppError> {
+    loop {
+        print!(
+            "{} ",
+            paint_magenta(&format!("replace with \"{}\"? [y/n/a/q]:", replacement))
+        );
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            // stdin closed, nothing else can be answered
+            return Ok(Decision::Quit);
+        }
+
+        match answer.trim() {
+            "y" | "Y" => return Ok(Decision::Yes),
+            "n" | "N" => return Ok(Decision::No),
+            "a" | "A" => return Ok(Decision::All),
+            "q" | "Q" => return Ok(Decision::Quit),
+            _ => println!("please answer y (yes), n (no), a (all remaining) or q (quit)"),
+        }
+    }
+}
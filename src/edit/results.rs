@@ -0,0 +1,184 @@
+use super::source::Source;
+use crate::{
+    errors::custom_errors::AppError,
+    output::printer::print_warning,
+    schemas::files::{FileResult, SearchResult},
+};
+use encoding_rs::Encoding;
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    process::{self, Command},
+};
+
+#[derive(Default)]
+pub struct EditSummary {
+    pub lines: usize,
+    pub files: usize,
+    pub conflicts: usize,
+}
+
+/// One editable line of the results file, remembered so the edited text can be traced back
+/// to the file and line it came from.
+struct Entry<'a> {
+    path: &'a Path,
+    result: &'a SearchResult,
+}
+
+/// Dumps every matching line as `path:line: text` into a temporary file, opens it in
+/// `$VISUAL`/`$EDITOR` and writes the lines whose text changed back to their files.
+///
+/// Before a line is overwritten it is compared with what the workers read. When the file
+/// changed on disk in the meantime the line is reported as a conflict and left alone.
+///
+/// Returns `None` without opening the editor when none of the results can be written back,
+/// like when nothing matched or only binary files and extracted content did.
+pub fn edit_results(
+    results: &[FileResult],
+    encoding: Option<&'static Encoding>,
+) -> Result<Option<EditSummary>, AppError> {
+    let mut entries: HashMap<String, Entry> = HashMap::new();
+    let mut dump = String::new();
+
+    for file in results.iter().filter(|f| f.is_editable()) {
+        let path = file.path.to_str().ok_or(AppError::InvalidPath)?;
+        for r in &file.results {
+            let prefix = format!("{}:{}: ", path, r.line);
+            dump.push_str(&prefix);
+            dump.push_str(shown_text(r));
+            dump.push('\n');
+            entries.insert(
+                prefix,
+                Entry {
+                    path: &file.path,
+                    result: r,
+                },
+            );
+        }
+    }
+
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let tmp = env::temp_dir().join(format!("sik-results-{}.txt", process::id()));
+    // the name can be guessed, so a file or symlink already there is never followed, and the
+    // lines are only readable by the user
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&tmp)?.write_all(dump.as_bytes())?;
+
+    let status = open_editor(&tmp);
+    let edited = fs::read_to_string(&tmp);
+    fs::remove_file(&tmp)?;
+
+    if !status?.success() {
+        print_warning("the editor exited with an error, nothing was written");
+        return Ok(Some(EditSummary::default()));
+    }
+
+    // edits grouped by file, each one holding the original line and its new text
+    let mut edits: HashMap<&Path, Vec<(&SearchResult, String)>> = HashMap::new();
+
+    for line in edited?.lines() {
+        let Some((prefix, text)) = split_entry(line, &entries) else {
+            if !line.trim().is_empty() {
+                print_warning(&format!("ignoring unrecognized line: {}", line));
+            }
+            continue;
+        };
+
+        let entry = &entries[prefix];
+        let shown = shown_text(entry.result);
+        if text != shown {
+            // the `\r` left in the line by --no-crlf goes back where it was
+            let cr = &entry.result.line_content[shown.len()..];
+            edits
+                .entry(entry.path)
+                .or_default()
+                .push((entry.result, format!("{}{}", text, cr)));
+        }
+    }
+
+    let mut summary = EditSummary::default();
+
+    for (path, changes) in edits {
+        let shown = path.display();
+
+        let mut source = match Source::read(path, encoding) {
+            Ok(source) => source,
+            Err(e) => {
+                print_warning(&format!("skipping {}: {}", shown, e));
+                summary.conflicts += changes.len();
+                continue;
+            }
+        };
+        let lines = &mut source.lines;
+        let mut written = 0;
+
+        for (r, text) in changes {
+            let idx = r.line - 1;
+            match lines.get(idx) {
+                Some(current) if *current == format!("{}{}", r.line_content, r.terminator) => {
+                    lines[idx] = format!("{}{}", text, r.terminator);
+                    written += 1;
+                }
+                _ => {
+                    print_warning(&format!(
+                        "conflict: {}:{} changed on disk since it was searched, not overwritten",
+                        shown, r.line
+                    ));
+                    summary.conflicts += 1;
+                }
+            }
+        }
+
+        if written > 0 {
+            match source.write(path) {
+                Ok(()) => {
+                    summary.lines += written;
+                    summary.files += 1;
+                }
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    print_warning(&format!("skipping {}: {}", shown, e));
+                    summary.conflicts += written;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    Ok(Some(summary))
+}
+
+/// The text of a line as it is dumped. A `\r` kept at its end with --no-crlf would be lost
+/// when the file is read back, and every line would look edited.
+fn shown_text(r: &SearchResult) -> &str {
+    r.line_content.strip_suffix('\r').unwrap_or(&r.line_content)
+}
+
+fn open_editor(file: &Path) -> Result<process::ExitStatus, AppError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // allows things like `EDITOR="code --wait"`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    Ok(Command::new(program).args(parts).arg(file).status()?)
+}
+
+/// Finds which known `path:line: ` prefix starts `line`. Paths may contain `: ` themselves, so
+/// every candidate separator is tried.
+fn split_entry<'l>(line: &'l str, entries: &HashMap<String, Entry>) -> Option<(&'l str, &'l str)> {
+    line.match_indices(": ").find_map(|(i, sep)| {
+        let (prefix, text) = line.split_at(i + sep.len());
+        entries.contains_key(prefix).then_some((prefix, text))
+    })
+}
@@ -0,0 +1,63 @@
+use crate::worker::detect_encoding;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use std::{fs, io, path::Path};
+
+/// A file being edited, cut in lines the way the workers number them. It is decoded with the
+/// encoding the workers read it with, and encoded back with it on writing. The workers never
+/// see the byte order mark, so it is set apart from the first line and put back as it was.
+pub struct Source {
+    encoding: &'static Encoding,
+    bom: Vec<u8>,
+    pub lines: Vec<String>,
+}
+
+impl Source {
+    /// Reads the file at `path`, `forced` being the encoding given with `-E/--encoding`. A file
+    /// with bytes that are not valid in its encoding is refused, since writing it back would
+    /// replace them.
+    pub fn read(path: &Path, forced: Option<&'static Encoding>) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let (encoding, bom_len) = detect_encoding(&bytes, forced);
+        let text = encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes[bom_len..])
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("not valid {}", encoding.name()),
+                )
+            })?;
+        Ok(Source {
+            encoding,
+            bom: bytes[..bom_len].to_vec(),
+            lines: text.split_inclusive('\n').map(String::from).collect(),
+        })
+    }
+
+    /// Writes the lines back in the encoding of the file. Fails with `InvalidData`, leaving the
+    /// file untouched, when the new text has characters the encoding cannot represent.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let text = self.lines.concat();
+        let mut bytes = self.bom.clone();
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            // encoding_rs only decodes UTF-16, its encoder would write UTF-8
+            let le = self.encoding == UTF_16LE;
+            for unit in text.encode_utf16() {
+                bytes.extend(if le {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                });
+            }
+        } else {
+            let (encoded, _, unmappable) = self.encoding.encode(&text);
+            if unmappable {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("the new text cannot be written in {}", self.encoding.name()),
+                ));
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+        fs::write(path, bytes)
+    }
+}
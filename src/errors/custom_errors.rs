@@ -8,11 +8,14 @@ use std::sync::{MutexGuard, PoisonError};
 #[derive(Debug)]
 pub enum AppError {
     Regex(regex::Error),
+    FancyRegex(Box<fancy_regex::Error>),
+    Glob(globset::Error),
     Io(io::Error),
     SendError(String),
     MutexPoisoned(String),
     ThreadPanic,
     InvalidPath,
+    InvalidPattern(String),
 }
 
 impl fmt::Display for AppError {
@@ -23,7 +26,10 @@ impl fmt::Display for AppError {
             AppError::MutexPoisoned(err) => write!(f, "Mutex poisoned: {}", err),
             AppError::ThreadPanic => write!(f, "A worker thread panicked"),
             AppError::InvalidPath => write!(f, "Invalid path encountered"),
+            AppError::InvalidPattern(err) => write!(f, "Invalid pattern: {}", err),
             AppError::Regex(err) => write!(f, "Regex error: {}", err),
+            AppError::FancyRegex(err) => write!(f, "Regex error: {}", err),
+            AppError::Glob(err) => write!(f, "Glob error: {}", err),
         }
     }
 }
@@ -33,6 +39,8 @@ impl Error for AppError {
         match self {
             AppError::Io(err) => Some(err),
             AppError::Regex(err) => Some(err),
+            AppError::FancyRegex(err) => Some(err.as_ref()),
+            AppError::Glob(err) => Some(err),
             _ => None,
         }
     }
@@ -61,3 +69,15 @@ impl From<regex::Error> for AppError {
         AppError::Regex(err)
     }
 }
+
+impl From<fancy_regex::Error> for AppError {
+    fn from(err: fancy_regex::Error) -> Self {
+        AppError::FancyRegex(Box::new(err))
+    }
+}
+
+impl From<globset::Error> for AppError {
+    fn from(err: globset::Error) -> Self {
+        AppError::Glob(err)
+    }
+}
@@ -0,0 +1,69 @@
+/// Text pulled out of a file that is not plain text, remembering which part of the file
+/// (paragraph, cell, page...) each of its lines comes from.
+#[derive(Default)]
+pub struct Document {
+    pub text: String,
+    sections: Vec<Section>,
+}
+
+struct Section {
+    label: String,
+    first_line: usize,
+    lines: usize,
+    /// The line within the section is shown even when it holds a single one.
+    numbered: bool,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Document::default()
+    }
+
+    /// Appends the text of a section on lines of its own. Blank sections are left out, they
+    /// can never match and would only push the next ones down.
+    pub fn push(&mut self, label: String, text: &str) {
+        self.push_section(label, text, false);
+    }
+
+    /// Like [`Document::push`], for sections whose locations always name the line, like the
+    /// cells of a notebook.
+    pub fn push_numbered(&mut self, label: String, text: &str) {
+        self.push_section(label, text, true);
+    }
+
+    fn push_section(&mut self, label: String, text: &str, numbered: bool) {
+        let text = text.trim_end_matches(['\r', '\n']);
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let first_line = self.sections.last().map_or(1, |s| s.first_line + s.lines);
+        let lines = text.lines().count();
+
+        for line in text.lines() {
+            self.text.push_str(line);
+            self.text.push('\n');
+        }
+        self.sections.push(Section {
+            label,
+            first_line,
+            lines,
+            numbered,
+        });
+    }
+
+    /// Where `line` of the text comes from: the label of its section, followed by the line
+    /// within the section when it spans several or is numbered.
+    pub fn location(&self, line: usize) -> String {
+        let index = self.sections.partition_point(|s| s.first_line <= line);
+        let Some(section) = index.checked_sub(1).map(|i| &self.sections[i]) else {
+            return format!("line {}", line);
+        };
+
+        if section.lines > 1 || section.numbered {
+            format!("{}, line {}", section.label, line - section.first_line + 1)
+        } else {
+            section.label.clone()
+        }
+    }
+}
@@ -0,0 +1,72 @@
+mod document;
+mod notebook;
+mod office;
+mod path_query;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod tree;
+mod xml;
+
+pub use document::Document;
+pub use path_query::PathQuery;
+pub use tree::DataFormat;
+
+use std::path::Path;
+
+/// Formats whose text sik pulls out by itself with `--docs`.
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    Docx,
+    Odt,
+    Xlsx,
+    Epub,
+    /// Jupyter notebooks (`.ipynb`).
+    Notebook,
+    #[cfg(feature = "pdf")]
+    Pdf,
+}
+
+impl Format {
+    /// Guessed from the extension: most of these are zip containers, their magic bytes don't
+    /// tell them apart.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "docx" => Some(Format::Docx),
+            "odt" => Some(Format::Odt),
+            "xlsx" => Some(Format::Xlsx),
+            "epub" => Some(Format::Epub),
+            "ipynb" => Some(Format::Notebook),
+            #[cfg(feature = "pdf")]
+            "pdf" => Some(Format::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// Pulls the visible text out of a file of the given format, `notebook_outputs` adding the
+/// outputs of notebook cells to their sources. Errors are meant to be shown as warnings, a
+/// broken document should not stop the search.
+pub fn extract(format: Format, bytes: &[u8], notebook_outputs: bool) -> Result<Document, String> {
+    match format {
+        Format::Docx => office::docx(bytes),
+        Format::Odt => office::odt(bytes),
+        Format::Xlsx => office::xlsx(bytes),
+        Format::Epub => office::epub(bytes),
+        Format::Notebook => notebook::notebook(bytes, notebook_outputs),
+        #[cfg(feature = "pdf")]
+        Format::Pdf => pdf::pdf(bytes),
+    }
+}
+
+/// Parses a JSON, YAML or TOML file and pulls out what `query` selects in it, the values or with
+/// `keys` the keys. Errors are meant to be shown as warnings, like the ones of [`extract`].
+pub fn select_paths(
+    format: DataFormat,
+    text: &str,
+    query: &PathQuery,
+    keys: bool,
+) -> Result<Document, String> {
+    let roots = tree::parse(format, text)?;
+    Ok(query.select(&roots, keys))
+}
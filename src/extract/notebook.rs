@@ -0,0 +1,65 @@
+use super::document::Document;
+use serde_json::Value;
+
+/// Jupyter notebooks: the source of the code and markdown cells, labelled `cell 12 (code),
+/// line 3`, and with `outputs` the text the code cells printed. Searching the raw JSON would
+/// match the escaped sources and the base64 of the images.
+pub fn notebook(bytes: &[u8], outputs: bool) -> Result<Document, String> {
+    let notebook: Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or("not a notebook in the nbformat 4 layout")?;
+    let mut doc = Document::new();
+
+    for (i, cell) in cells.iter().enumerate() {
+        let kind = cell.get("cell_type").and_then(Value::as_str).unwrap_or("");
+        if kind != "code" && kind != "markdown" {
+            continue;
+        }
+
+        doc.push_numbered(
+            format!("cell {} ({})", i + 1, kind),
+            &multiline(cell.get("source")),
+        );
+
+        if !outputs {
+            continue;
+        }
+        for output in cell
+            .get("outputs")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            doc.push_numbered(format!("cell {} (output)", i + 1), &output_text(output));
+        }
+    }
+
+    Ok(doc)
+}
+
+/// Notebooks store text either as a string or as a list of lines, each keeping its `\n`.
+fn multiline(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// The text of an output: what was printed, the plain text version of a result, or the error.
+/// Images and HTML are left out.
+fn output_text(output: &Value) -> String {
+    match output.get("output_type").and_then(Value::as_str) {
+        Some("stream") => multiline(output.get("text")),
+        Some("execute_result" | "display_data") => {
+            multiline(output.get("data").and_then(|d| d.get("text/plain")))
+        }
+        Some("error") => {
+            let field = |name| output.get(name).and_then(Value::as_str).unwrap_or("");
+            format!("{}: {}", field("ename"), field("evalue"))
+        }
+        _ => String::new(),
+    }
+}
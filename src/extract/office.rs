@@ -0,0 +1,423 @@
+use super::{
+    document::Document,
+    xml::{attribute, text},
+};
+use quick_xml::{Reader, events::Event};
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+};
+use zip::ZipArchive;
+
+type Container<'a> = ZipArchive<Cursor<&'a [u8]>>;
+
+/// Parts bigger than this are refused, a few KiB of deflated XML can unpack into a lot.
+const MAX_PART_SIZE: u64 = 64 * 1024 * 1024;
+
+fn open(bytes: &[u8]) -> Result<Container<'_>, String> {
+    ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())
+}
+
+/// Reads the part `name` of the container, `Ok(None)` when there is no such part.
+fn read_part(zip: &mut Container, name: &str) -> Result<Option<String>, String> {
+    let part = match zip.by_name(name) {
+        Ok(part) => part,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut xml = String::new();
+    part.take(MAX_PART_SIZE + 1)
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    if xml.len() as u64 > MAX_PART_SIZE {
+        return Err(format!("{} is too large", name));
+    }
+    Ok(Some(xml))
+}
+
+fn require_part(zip: &mut Container, name: &str) -> Result<String, String> {
+    read_part(zip, name)?.ok_or_else(|| format!("{} is missing", name))
+}
+
+fn next<'a>(reader: &mut Reader<&'a [u8]>) -> Result<Event<'a>, String> {
+    reader.read_event().map_err(|e| e.to_string())
+}
+
+/// Word documents: each `w:p` is a paragraph, table cells included.
+pub fn docx(bytes: &[u8]) -> Result<Document, String> {
+    let xml = require_part(&mut open(bytes)?, "word/document.xml")?;
+    let mut reader = Reader::from_str(&xml);
+    let mut doc = Document::new();
+    let mut paragraph = String::new();
+    let mut count = 0;
+    // text is only taken from runs, `w:tab` also appears in the tab stops of a paragraph
+    let mut in_run = false;
+    let mut in_text = false;
+
+    loop {
+        let event = next(&mut reader)?;
+        match &event {
+            Event::Start(e) => match e.name().as_ref() {
+                "w:r" => in_run = true,
+                "w:t" => in_text = in_run,
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                "w:tab" if in_run => paragraph.push('\t'),
+                "w:br" | "w:cr" if in_run => paragraph.push('\n'),
+                "w:p" => count += 1,
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                "w:r" => in_run = false,
+                "w:t" => in_text = false,
+                "w:p" => {
+                    count += 1;
+                    doc.push(format!("paragraph {}", count), &paragraph);
+                    paragraph.clear();
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            event if in_text => {
+                if let Some(t) = text(event) {
+                    paragraph.push_str(&t);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(doc)
+}
+
+/// OpenDocument text: paragraphs (`text:p`) and headings (`text:h`), with the spaces, tabs
+/// and line breaks that ODF stores as elements put back.
+pub fn odt(bytes: &[u8]) -> Result<Document, String> {
+    let xml = require_part(&mut open(bytes)?, "content.xml")?;
+    let mut reader = Reader::from_str(&xml);
+    let mut doc = Document::new();
+    let mut paragraph = String::new();
+    let mut count = 0;
+    // paragraphs can nest (notes, frames), the inner text goes with the outer paragraph
+    let mut depth = 0;
+
+    loop {
+        let event = next(&mut reader)?;
+        match &event {
+            Event::Start(e) if matches!(e.name().as_ref(), "text:p" | "text:h") => depth += 1,
+            Event::Empty(e) => match e.name().as_ref() {
+                "text:p" | "text:h" if depth == 0 => count += 1,
+                "text:s" if depth > 0 => {
+                    let spaces = attribute(e, "text:c").and_then(|c| c.parse().ok());
+                    paragraph.push_str(&" ".repeat(spaces.unwrap_or(1)));
+                }
+                "text:tab" if depth > 0 => paragraph.push('\t'),
+                "text:line-break" if depth > 0 => paragraph.push('\n'),
+                _ => {}
+            },
+            Event::End(e) if matches!(e.name().as_ref(), "text:p" | "text:h") => {
+                depth -= 1;
+                if depth == 0 {
+                    count += 1;
+                    doc.push(format!("paragraph {}", count), &paragraph);
+                    paragraph.clear();
+                }
+            }
+            Event::Eof => break,
+            event if depth > 0 => {
+                if let Some(t) = text(event) {
+                    paragraph.push_str(&t);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(doc)
+}
+
+/// Excel workbooks: every non-empty cell of every sheet, labelled `Sheet!B4`. Numbers are
+/// taken as stored, without the formatting Excel would show.
+pub fn xlsx(bytes: &[u8]) -> Result<Document, String> {
+    let mut zip = open(bytes)?;
+    let shared = match read_part(&mut zip, "xl/sharedStrings.xml")? {
+        Some(xml) => shared_strings(&xml)?,
+        None => Vec::new(),
+    };
+    let targets = relationships(&require_part(&mut zip, "xl/_rels/workbook.xml.rels")?)?;
+    let mut doc = Document::new();
+
+    for (name, id) in sheets(&require_part(&mut zip, "xl/workbook.xml")?)? {
+        let Some(target) = targets.get(&id) else {
+            continue;
+        };
+        let part = match target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("xl/{}", target),
+        };
+        let xml = require_part(&mut zip, &part)?;
+        sheet_cells(&xml, &name, &shared, &mut doc)?;
+    }
+
+    Ok(doc)
+}
+
+/// Name and relationship id of each sheet, in the order of the workbook.
+fn sheets(xml: &str) -> Result<Vec<(String, String)>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut sheets = Vec::new();
+
+    loop {
+        match next(&mut reader)? {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == "sheet" => {
+                if let (Some(name), Some(id)) = (attribute(&e, "name"), attribute(&e, "r:id")) {
+                    sheets.push((name, id));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(sheets)
+}
+
+fn relationships(xml: &str) -> Result<HashMap<String, String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut targets = HashMap::new();
+
+    loop {
+        match next(&mut reader)? {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == "Relationship" => {
+                if let (Some(id), Some(target)) = (attribute(&e, "Id"), attribute(&e, "Target")) {
+                    targets.insert(id, target);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(targets)
+}
+
+/// The shared string table, which the cells of type `s` point into. A string made of several
+/// formatted runs has one `t` per run.
+fn shared_strings(xml: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+
+    loop {
+        let event = next(&mut reader)?;
+        match &event {
+            Event::Start(e) if e.name().as_ref() == "t" => in_text = true,
+            Event::End(e) => match e.name().as_ref() {
+                "t" => in_text = false,
+                "si" => strings.push(std::mem::take(&mut current)),
+                _ => {}
+            },
+            Event::Empty(e) if e.name().as_ref() == "si" => strings.push(String::new()),
+            Event::Eof => break,
+            event if in_text => {
+                if let Some(t) = text(event) {
+                    current.push_str(&t);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(strings)
+}
+
+fn sheet_cells(
+    xml: &str,
+    sheet: &str,
+    shared: &[String],
+    doc: &mut Document,
+) -> Result<(), String> {
+    let mut reader = Reader::from_str(xml);
+    // reference and type of the cell being read
+    let mut cell: Option<(String, String)> = None;
+    let mut value = String::new();
+    let mut in_value = false;
+
+    loop {
+        let event = next(&mut reader)?;
+        match &event {
+            Event::Start(e) => match e.name().as_ref() {
+                "c" => {
+                    let reference = attribute(e, "r").unwrap_or_default();
+                    let kind = attribute(e, "t").unwrap_or_default();
+                    cell = Some((reference, kind));
+                }
+                // `v` holds the value, `t` the text of an inline string
+                "v" | "t" => in_value = cell.is_some(),
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                "v" | "t" => in_value = false,
+                "c" => {
+                    if let Some((reference, kind)) = cell.take() {
+                        let shown = match kind.as_str() {
+                            "s" => value
+                                .trim()
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|i| shared.get(i))
+                                .map_or("", String::as_str),
+                            _ => &value,
+                        };
+                        doc.push(format!("{}!{}", sheet, reference), shown);
+                    }
+                    value.clear();
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            event if in_value => {
+                if let Some(t) = text(event) {
+                    value.push_str(&t);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// HTML elements whose content is shown as a block of its own.
+const BLOCKS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "td",
+    "th",
+    "dt",
+    "dd",
+    "pre",
+    "blockquote",
+    "div",
+    "caption",
+    "figcaption",
+];
+
+/// EPUB books: the chapters in reading order (the spine of the package), each block of text
+/// labelled with its chapter file, like `chapter02.xhtml, paragraph 5`.
+pub fn epub(bytes: &[u8]) -> Result<Document, String> {
+    let mut zip = open(bytes)?;
+    let package = rootfile(&require_part(&mut zip, "META-INF/container.xml")?)?;
+    let base = match package.rfind('/') {
+        Some(i) => &package[..=i],
+        None => "",
+    };
+    let mut doc = Document::new();
+
+    for href in spine(&require_part(&mut zip, &package)?)? {
+        let Some(xhtml) = read_part(&mut zip, &format!("{}{}", base, href))? else {
+            continue;
+        };
+        chapter(&xhtml, &href, &mut doc)?;
+    }
+
+    Ok(doc)
+}
+
+fn rootfile(xml: &str) -> Result<String, String> {
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        match next(&mut reader)? {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == "rootfile" => {
+                if let Some(path) = attribute(&e, "full-path") {
+                    return Ok(path);
+                }
+            }
+            Event::Eof => return Err("the container has no rootfile".to_string()),
+            _ => {}
+        }
+    }
+}
+
+/// Paths, relative to the package, of the documents listed in the spine.
+fn spine(xml: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut manifest = HashMap::new();
+    let mut order = Vec::new();
+
+    loop {
+        match next(&mut reader)? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                "item" => {
+                    if let (Some(id), Some(href)) = (attribute(&e, "id"), attribute(&e, "href")) {
+                        manifest.insert(id, href);
+                    }
+                }
+                "itemref" => order.extend(attribute(&e, "idref")),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(order
+        .iter()
+        .filter_map(|id| manifest.get(id).cloned())
+        .collect())
+}
+
+fn chapter(xhtml: &str, href: &str, doc: &mut Document) -> Result<(), String> {
+    let mut reader = Reader::from_str(xhtml);
+    let mut block = String::new();
+    let mut count = 0;
+    // inside <head>, <script> or <style>, nothing there is shown
+    let mut hidden = 0;
+
+    let mut flush = |block: &mut String, count: &mut usize| {
+        // HTML collapses the whitespace of the markup
+        let collapsed = block.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !collapsed.is_empty() {
+            *count += 1;
+            doc.push(format!("{}, paragraph {}", href, count), &collapsed);
+        }
+        block.clear();
+    };
+
+    loop {
+        let event = next(&mut reader)?;
+        match &event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                "head" | "script" | "style" => hidden += 1,
+                name if BLOCKS.contains(&name) => flush(&mut block, &mut count),
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                "head" | "script" | "style" => hidden -= 1,
+                name if BLOCKS.contains(&name) => flush(&mut block, &mut count),
+                _ => {}
+            },
+            Event::Empty(e) if e.local_name().as_ref() == "br" => block.push(' '),
+            Event::Eof => break,
+            event if hidden == 0 => {
+                if let Some(t) = text(event) {
+                    block.push_str(&t);
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut block, &mut count);
+
+    Ok(())
+}
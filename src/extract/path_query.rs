@@ -0,0 +1,193 @@
+use super::{
+    document::Document,
+    tree::{Kind, Node},
+};
+
+#[derive(Clone, Debug)]
+enum Step {
+    /// `.name` or `['name']`.
+    Key(String),
+    /// `[3]`.
+    Index(usize),
+    /// `.*` or `[*]`, every entry of a map or item of a list.
+    Any,
+    /// `..`, the node itself and everything beneath it.
+    Descendants,
+}
+
+/// A JSONPath-like selection of values, like `$.dependencies.*.version` or `$..image`.
+#[derive(Clone, Debug)]
+pub struct PathQuery(Vec<Step>);
+
+impl PathQuery {
+    /// Reads `$` followed by `.key`, `.*`, `..`, `[2]`, `[*]` and `['key with dots']` steps.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let invalid = |why: &str| format!("invalid path query '{}': {}", query, why);
+        let mut rest = query
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("it must start with '$'"))?;
+        let mut steps = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                steps.push(Step::Descendants);
+                // `$..name` is short for `$...name`
+                rest = if after.starts_with('[') {
+                    after
+                } else {
+                    &rest[1..]
+                };
+                if rest.is_empty() || rest == "." {
+                    return Err(invalid("it cannot end with '..'"));
+                }
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let len = after.find(['.', '[']).unwrap_or(after.len());
+                steps.push(match &after[..len] {
+                    "" => return Err(invalid("a key is missing after '.'")),
+                    "*" => Step::Any,
+                    key => Step::Key(key.to_string()),
+                });
+                rest = &after[len..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (step, len) = bracket(after).ok_or_else(|| invalid("unclosed or empty '['"))?;
+                steps.push(step);
+                rest = &after[len..];
+            } else {
+                return Err(invalid("expected '.' or '[' between the steps"));
+            }
+        }
+
+        Ok(PathQuery(steps))
+    }
+
+    /// Builds the text searched for a file: the scalars at the selected paths, or with `keys`
+    /// the keys of the selected entries. Each of its lines is labelled with its path and the
+    /// line of the file it starts on, and they come in the order of the file.
+    pub fn select(&self, roots: &[Node], keys: bool) -> Document {
+        let mut selected = Vec::new();
+        for root in roots {
+            walk(root, &self.0, "$".to_string(), None, &mut selected);
+        }
+
+        let mut found = Vec::new();
+        for (path, node, key) in selected {
+            if !keys {
+                scalars(node, path, &mut found);
+            } else if let Some((key, line)) = key {
+                found.push((line, path, key.to_string()));
+            }
+        }
+        // `..` can reach the same node along two routes
+        found.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        found.dedup();
+
+        let mut doc = Document::new();
+        for (line, path, text) in found {
+            let label = format!("{} (line {})", path, line);
+            for text_line in text.lines() {
+                doc.push(label.clone(), text_line);
+            }
+        }
+        doc
+    }
+}
+
+/// The step between brackets and the length read, closing bracket included.
+fn bracket(after: &str) -> Option<(Step, usize)> {
+    if let Some(quote) = after.chars().next().filter(|c| *c == '\'' || *c == '"') {
+        let end = after[1..].find(quote)? + 1;
+        after[end + 1..].starts_with(']').then(|| {
+            let key = after[1..end].to_string();
+            (Step::Key(key), end + 2)
+        })
+    } else {
+        let end = after.find(']')?;
+        let step = match after[..end].trim() {
+            "*" => Step::Any,
+            index => Step::Index(index.parse().ok()?),
+        };
+        Some((step, end + 1))
+    }
+}
+
+/// A node reached by the query, with its path and the key it is stored under, if any.
+type Selected<'a> = (String, &'a Node, Option<(&'a str, usize)>);
+
+fn walk<'a>(
+    node: &'a Node,
+    steps: &[Step],
+    path: String,
+    key: Option<(&'a str, usize)>,
+    out: &mut Vec<Selected<'a>>,
+) {
+    let Some((step, rest)) = steps.split_first() else {
+        out.push((path, node, key));
+        return;
+    };
+
+    match (step, &node.kind) {
+        (Step::Descendants, _) => {
+            walk(node, rest, path.clone(), key, out);
+            for (child_path, child, child_key) in children(node, &path) {
+                walk(child, steps, child_path, child_key, out);
+            }
+        }
+        (Step::Any, _) => {
+            for (child_path, child, child_key) in children(node, &path) {
+                walk(child, rest, child_path, child_key, out);
+            }
+        }
+        (Step::Key(name), Kind::Map(entries)) => {
+            for (key, line, value) in entries.iter().filter(|(key, ..)| key == name) {
+                walk(value, rest, key_path(&path, key), Some((key, *line)), out);
+            }
+        }
+        (Step::Index(index), Kind::List(items)) => {
+            if let Some(item) = items.get(*index) {
+                walk(item, rest, format!("{}[{}]", path, index), None, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn children<'a>(node: &'a Node, path: &str) -> Vec<Selected<'a>> {
+    match &node.kind {
+        Kind::Scalar(_) => Vec::new(),
+        Kind::List(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (format!("{}[{}]", path, i), item, None))
+            .collect(),
+        Kind::Map(entries) => entries
+            .iter()
+            .map(|(key, line, value)| (key_path(path, key), value, Some((key.as_str(), *line))))
+            .collect(),
+    }
+}
+
+/// Collects the scalars of `node`, the node itself when it is one.
+fn scalars(node: &Node, path: String, out: &mut Vec<(usize, String, String)>) {
+    match &node.kind {
+        Kind::Scalar(value) => out.push((node.line, path, value.clone())),
+        _ => {
+            for (child_path, child, _) in children(node, &path) {
+                scalars(child, child_path, out);
+            }
+        }
+    }
+}
+
+/// Keys that would not read back as a `.key` step are written in brackets.
+fn key_path(path: &str, key: &str) -> String {
+    let plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if plain {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}['{}']", path, key)
+    }
+}
@@ -0,0 +1,37 @@
+use super::document::Document;
+use pdf_extract::{Error, OutputError};
+use std::panic::{self, AssertUnwindSafe};
+
+/// PDFs: the text of each page, each line labelled `page N, line M`, even on a page that
+/// holds a single one. Encrypted documents and scans without a text layer come back as
+/// errors, so they end up as warnings.
+pub fn pdf(bytes: &[u8]) -> Result<Document, String> {
+    // the extractor still panics on some malformed files, that should cost a warning and not
+    // a worker
+    let pages = panic::catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::extract_text_from_mem_by_pages(bytes)
+    }))
+    .map_err(|_| "the PDF could not be parsed".to_string())?
+    .map_err(|e| match e {
+        OutputError::PdfError(Error::Decryption(_)) => {
+            "the PDF is encrypted, it cannot be read without its password".to_string()
+        }
+        e => e.to_string(),
+    })?;
+
+    if pages.iter().all(|page| page.trim().is_empty()) {
+        return Err("the PDF has no text layer (a scan or images only)".to_string());
+    }
+
+    let mut doc = Document::new();
+    for (i, page) in pages.iter().enumerate() {
+        // the extractor spaces text blocks out with blank lines, without them the line
+        // numbers count the lines printed on the page
+        let lines = page.lines().filter(|l| !l.trim().is_empty());
+        for (k, line) in lines.enumerate() {
+            doc.push(format!("page {}, line {}", i + 1, k + 1), line);
+        }
+    }
+
+    Ok(doc)
+}
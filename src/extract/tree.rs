@@ -0,0 +1,623 @@
+use std::{collections::HashMap, path::Path};
+use toml_edit::{Item, Table, Value};
+use yaml_rust2::{
+    Event,
+    parser::{MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+/// Structured formats `--path-query` reads.
+#[derive(Clone, Copy, Debug)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    pub fn from_path(path: &Path) -> Option<DataFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(DataFormat::Json),
+            "yaml" | "yml" => Some(DataFormat::Yaml),
+            "toml" => Some(DataFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// A value of a structured file, with the line it starts on.
+pub struct Node {
+    pub line: usize,
+    pub kind: Kind,
+}
+
+pub enum Kind {
+    /// Strings, numbers, booleans and nulls, as written.
+    Scalar(String),
+    List(Vec<Node>),
+    /// The entries in the order of the file, each with the line of its key.
+    Map(Vec<(String, usize, Node)>),
+}
+
+/// Parses `text` into one tree per document, YAML streams being the only ones holding
+/// several.
+pub fn parse(format: DataFormat, text: &str) -> Result<Vec<Node>, String> {
+    match format {
+        DataFormat::Json => Json::new(text).document().map(|node| vec![node]),
+        DataFormat::Yaml => yaml(text),
+        DataFormat::Toml => toml(text).map(|node| vec![node]),
+    }
+}
+
+// arrays nested deeper than this are surely not a config, and would overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// serde_json throws the positions away, this parser keeps the line of every value.
+struct Json<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Json<'a> {
+    fn new(text: &'a str) -> Self {
+        Json {
+            text,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn document(mut self) -> Result<Node, String> {
+        let node = self.value(0)?;
+        self.skip_whitespace();
+        if self.pos < self.text.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(node)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at line {}", message, self.line)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b) = self.peek() {
+            match b {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Node, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deep"));
+        }
+        self.skip_whitespace();
+        let line = self.line;
+
+        let kind = match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key_line = self.line;
+                        let key = self.string()?;
+                        self.expect(b':')?;
+                        entries.push((key, key_line, self.value(depth + 1)?));
+                        if !self.separator(b'}')? {
+                            break;
+                        }
+                    }
+                }
+                Kind::Map(entries)
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if !self.separator(b']')? {
+                            break;
+                        }
+                    }
+                }
+                Kind::List(items)
+            }
+            Some(b'"') => Kind::Scalar(self.string()?),
+            Some(_) => {
+                // numbers and literals are kept as written, they are only ever searched
+                let rest = &self.text[self.pos..];
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c)))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(self.error("expected a value"));
+                }
+                self.pos += len;
+                Kind::Scalar(rest[..len].to_string())
+            }
+            None => return Err(self.error("unexpected end of file")),
+        };
+
+        Ok(Node { line, kind })
+    }
+
+    /// Reads the `,` before the next element, or the bracket closing the container, in which
+    /// case it returns false.
+    fn separator(&mut self, close: u8) -> Result<bool, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(b) if b == close => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(self.error(&format!("expected ',' or '{}'", close as char))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+
+        let text = self.text;
+        let mut out = String::new();
+        let mut chars = text[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let mut code = hex_unit(&mut chars);
+                            // characters outside the BMP are escaped as a surrogate pair
+                            if let Some(high @ 0xd800..0xdc00) = code {
+                                let low = match (chars.next(), chars.next()) {
+                                    (Some((_, '\\')), Some((_, 'u'))) => hex_unit(&mut chars),
+                                    _ => None,
+                                };
+                                code = low
+                                    .filter(|low| (0xdc00..0xe000).contains(low))
+                                    .map(|low| 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00));
+                            }
+                            let code = code.ok_or_else(|| self.error("invalid \\u escape"))?;
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        Some(c) => c,
+                        None => break,
+                    };
+                    out.push(escaped);
+                }
+                '\n' => break,
+                c => out.push(c),
+            }
+        }
+
+        Err(self.error("unterminated string"))
+    }
+}
+
+/// The four hex digits of a `\u` escape.
+fn hex_unit(chars: &mut std::str::CharIndices) -> Option<u32> {
+    let hex: String = chars.take(4).map(|(_, c)| c).collect();
+    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+/// Collects the events of the YAML parser along with their position.
+#[derive(Default)]
+struct Events(Vec<(Event, Marker)>);
+
+impl MarkedEventReceiver for Events {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        self.0.push((event, mark));
+    }
+}
+
+fn yaml(text: &str) -> Result<Vec<Node>, String> {
+    let mut events = Events::default();
+    Parser::new_from_str(text)
+        .load(&mut events, true)
+        .map_err(|e| e.to_string())?;
+
+    let mut builder = YamlBuilder {
+        events: events.0.into_iter(),
+        anchors: HashMap::new(),
+        built: 0,
+        deepest: 0,
+    };
+    let mut documents = Vec::new();
+    while let Some((event, mark)) = builder.events.next() {
+        if !matches!(
+            event,
+            Event::StreamStart | Event::StreamEnd | Event::DocumentStart | Event::DocumentEnd
+        ) {
+            documents.push(builder.node(event, mark, 0)?);
+        }
+    }
+
+    Ok(documents)
+}
+
+struct YamlBuilder {
+    events: std::vec::IntoIter<(Event, Marker)>,
+    /// Nodes with an anchor, which aliases stand for, with the number of nodes in them and how
+    /// many levels they nest below themselves.
+    anchors: HashMap<usize, (Node, usize, usize)>,
+    /// Nodes built so far, aliases counting for the nodes they are expanded to.
+    built: usize,
+    /// Deepest level reached by the node being built, aliases counting for their expanded depth.
+    deepest: usize,
+}
+
+// a few hundred bytes of nested aliases expand to billions of nodes ("billion laughs"), no
+// real config comes close to this
+const MAX_YAML_NODES: usize = 500_000;
+
+impl YamlBuilder {
+    fn next(&mut self) -> Result<(Event, Marker), String> {
+        self.events
+            .next()
+            .ok_or_else(|| "unexpected end of the YAML stream".to_string())
+    }
+
+    fn node(&mut self, event: Event, mark: Marker, depth: usize) -> Result<Node, String> {
+        let line = mark.line();
+        if depth > MAX_DEPTH {
+            return Err(format!("nested too deep at line {}", line));
+        }
+        let first = self.built;
+        self.built += 1;
+        let outer = std::mem::replace(&mut self.deepest, depth);
+
+        let (kind, anchor) = match event {
+            Event::Scalar(value, _, anchor, _) => (Kind::Scalar(value), anchor),
+            Event::SequenceStart(anchor, _) => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next()? {
+                        (Event::SequenceEnd, _) => break,
+                        (event, mark) => items.push(self.node(event, mark, depth + 1)?),
+                    }
+                }
+                (Kind::List(items), anchor)
+            }
+            Event::MappingStart(anchor, _) => {
+                let mut entries = Vec::new();
+                let mut merged = Vec::new();
+                loop {
+                    let (event, key_mark) = match self.next()? {
+                        (Event::MappingEnd, _) => break,
+                        next => next,
+                    };
+                    // complex keys are rare enough to be shown by their text only
+                    let key = match self.node(event, key_mark, depth + 1)?.kind {
+                        Kind::Scalar(key) => key,
+                        _ => "?".to_string(),
+                    };
+                    let (event, mark) = self.next()?;
+                    let value = self.node(event, mark, depth + 1)?;
+
+                    // `<<: *base` brings in the entries of other mappings, the ones written in
+                    // this mapping taking precedence
+                    match (key.as_str(), value.kind) {
+                        ("<<", Kind::Map(from)) => merged.extend(from),
+                        ("<<", Kind::List(maps)) => {
+                            for map in maps {
+                                if let Kind::Map(from) = map.kind {
+                                    merged.extend(from);
+                                }
+                            }
+                        }
+                        (_, kind) => entries.push((key, key_mark.line(), Node { kind, ..value })),
+                    }
+                }
+                for entry in merged {
+                    if !entries.iter().any(|(key, ..)| *key == entry.0) {
+                        entries.push(entry);
+                    }
+                }
+                (Kind::Map(entries), anchor)
+            }
+            Event::Alias(id) => {
+                let (node, size, height) = self
+                    .anchors
+                    .get(&id)
+                    .ok_or_else(|| format!("unknown alias at line {}", line))?;
+                // each alias nests its whole anchor at this depth, which `copy` and the searches
+                // over the tree recurse through
+                if depth + height > MAX_DEPTH {
+                    return Err(format!("nested too deep at line {}", line));
+                }
+                self.deepest = outer.max(depth + height);
+                self.built += size - 1;
+                if self.built > MAX_YAML_NODES {
+                    return Err(format!(
+                        "the aliases expand to more than {} values at line {}",
+                        MAX_YAML_NODES, line
+                    ));
+                }
+                return Ok(copy(node));
+            }
+            _ => return Err(format!("unexpected YAML event at line {}", line)),
+        };
+
+        let node = Node { line, kind };
+        if anchor > 0 {
+            self.anchors.insert(
+                anchor,
+                (copy(&node), self.built - first, self.deepest - depth),
+            );
+        }
+        self.deepest = self.deepest.max(outer);
+        Ok(node)
+    }
+}
+
+fn copy(node: &Node) -> Node {
+    let kind = match &node.kind {
+        Kind::Scalar(value) => Kind::Scalar(value.clone()),
+        Kind::List(items) => Kind::List(items.iter().map(copy).collect()),
+        Kind::Map(entries) => Kind::Map(
+            entries
+                .iter()
+                .map(|(key, line, value)| (key.clone(), *line, copy(value)))
+                .collect(),
+        ),
+    };
+    Node {
+        line: node.line,
+        kind,
+    }
+}
+
+fn toml(text: &str) -> Result<Node, String> {
+    let doc = toml_edit::Document::parse(text).map_err(|e| match e.span() {
+        Some(span) => format!("{} at line {}", e.message(), line_at(text, span.start)),
+        None => e.message().to_string(),
+    })?;
+    Ok(toml_table(text, doc.as_table(), 1))
+}
+
+/// Line of the byte at `offset`.
+fn line_at(text: &str, offset: usize) -> usize {
+    text.as_bytes()[..offset.min(text.len())]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+        + 1
+}
+
+fn toml_table(text: &str, table: &Table, line: usize) -> Node {
+    let entries = table
+        .iter()
+        .filter_map(|(name, _)| table.get_key_value(name))
+        .map(|(key, item)| {
+            // the implicit tables of dotted keys and `[a.b]` headers have no span of their own
+            let key_line = key.span().map_or(line, |span| line_at(text, span.start));
+            (
+                key.get().to_string(),
+                key_line,
+                toml_item(text, item, key_line),
+            )
+        })
+        .collect();
+
+    Node {
+        line,
+        kind: Kind::Map(entries),
+    }
+}
+
+fn toml_item(text: &str, item: &Item, parent_line: usize) -> Node {
+    let line = item
+        .span()
+        .map_or(parent_line, |span| line_at(text, span.start));
+    match item {
+        Item::Value(value) => toml_value(text, value, line),
+        Item::Table(table) => toml_table(text, table, line),
+        Item::ArrayOfTables(tables) => Node {
+            line,
+            kind: Kind::List(
+                tables
+                    .iter()
+                    .map(|table| {
+                        let line = table.span().map_or(line, |span| line_at(text, span.start));
+                        toml_table(text, table, line)
+                    })
+                    .collect(),
+            ),
+        },
+        Item::None => Node {
+            line,
+            kind: Kind::Scalar(String::new()),
+        },
+    }
+}
+
+fn toml_value(text: &str, value: &Value, line: usize) -> Node {
+    let kind = match value {
+        Value::String(s) => Kind::Scalar(s.value().clone()),
+        Value::Integer(i) => Kind::Scalar(i.value().to_string()),
+        Value::Float(f) => Kind::Scalar(f.value().to_string()),
+        Value::Boolean(b) => Kind::Scalar(b.value().to_string()),
+        Value::Datetime(d) => Kind::Scalar(d.value().to_string()),
+        Value::Array(array) => Kind::List(
+            array
+                .iter()
+                .map(|value| {
+                    let line = value.span().map_or(line, |span| line_at(text, span.start));
+                    toml_value(text, value, line)
+                })
+                .collect(),
+        ),
+        Value::InlineTable(table) => Kind::Map(
+            table
+                .iter()
+                .filter_map(|(name, _)| table.get_key_value(name))
+                .map(|(key, item)| {
+                    let key_line = key.span().map_or(line, |span| line_at(text, span.start));
+                    (
+                        key.get().to_string(),
+                        key_line,
+                        toml_item(text, item, key_line),
+                    )
+                })
+                .collect(),
+        ),
+    };
+    Node { line, kind }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(text: &str) -> Result<Node, String> {
+        Json::new(text).document()
+    }
+
+    /// The scalar stored under `key` at the root of a JSON object.
+    fn string_at(text: &str, key: &str) -> String {
+        let Kind::Map(entries) = json(text).unwrap().kind else {
+            panic!("not an object");
+        };
+        match &entries.iter().find(|(k, ..)| k == key).unwrap().2.kind {
+            Kind::Scalar(value) => value.clone(),
+            _ => panic!("not a scalar"),
+        }
+    }
+
+    #[test]
+    fn reads_escapes() {
+        let text = r#"{"a": "tab\tquote\"slash\/back\\nl\nu\u00e9"}"#;
+        assert_eq!(string_at(text, "a"), "tab\tquote\"slash/back\\nl\nué");
+    }
+
+    #[test]
+    fn joins_surrogate_pairs() {
+        assert_eq!(string_at(r#"{"a": "\ud83d\ude00"}"#, "a"), "😀");
+    }
+
+    #[test]
+    fn lone_low_surrogate_is_replaced() {
+        assert_eq!(string_at(r#"{"a": "\udc00"}"#, "a"), "\u{fffd}");
+    }
+
+    #[test]
+    fn rejects_broken_surrogate_pairs() {
+        for text in [
+            r#"{"a": "\ud800\u0000"}"#,
+            r#"{"a": "\ud800\uffff"}"#,
+            r#"{"a": "\ud800\ud800"}"#,
+            r#"{"a": "\ud800x"}"#,
+        ] {
+            let error = json(text).err().unwrap();
+            assert!(
+                error.starts_with("invalid \\u escape"),
+                "{}: {}",
+                text,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        for text in [
+            r#"{"a": "\u+fff"}"#,
+            r#"{"a": "\u12"}"#,
+            r#"{"a": "\uzzzz"}"#,
+        ] {
+            assert!(json(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let deep = |n| format!("{}{}", "[".repeat(n), "]".repeat(n));
+        assert!(json(&deep(MAX_DEPTH)).is_ok());
+        let error = json(&deep(MAX_DEPTH + 2)).err().unwrap();
+        assert!(error.starts_with("nested too deep"), "{}", error);
+    }
+
+    #[test]
+    fn limits_nesting_through_aliases() {
+        // every anchor nests the previous one's alias a level deeper, no line of it deep itself
+        let chain = |anchors: usize| {
+            let mut text = "a0: &a0 x\n".to_string();
+            for i in 1..anchors {
+                text.push_str(&format!("a{}: &a{} [[*a{}]]\n", i, i, i - 1));
+            }
+            text
+        };
+        assert!(yaml(&chain(MAX_DEPTH / 2)).is_ok());
+        let error = yaml(&chain(MAX_DEPTH)).err().unwrap();
+        assert!(error.starts_with("nested too deep"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_trailing_data() {
+        assert!(json("{} \n").is_ok());
+        let error = json("{}\n{}").err().unwrap();
+        assert_eq!(error, "trailing characters at line 2");
+    }
+
+    #[test]
+    fn keeps_lines() {
+        let Kind::Map(entries) = json("{\n  \"a\": 1,\n  \"b\": [\n    2\n  ]\n}")
+            .unwrap()
+            .kind
+        else {
+            panic!("not an object");
+        };
+        assert_eq!(entries[0].1, 2);
+        assert_eq!(entries[1].1, 3);
+        let Kind::List(items) = &entries[1].2.kind else {
+            panic!("not a list");
+        };
+        assert_eq!(items[0].line, 4);
+    }
+}
@@ -0,0 +1,43 @@
+use quick_xml::{
+    XmlVersion,
+    events::{BytesStart, Event},
+};
+use std::borrow::Cow;
+
+/// Text carried by an event: character data, or an entity reference resolved to its
+/// character. The XHTML of EPUBs uses a few HTML entities besides the XML ones.
+pub fn text<'a>(event: &'a Event) -> Option<Cow<'a, str>> {
+    match event {
+        Event::Text(text) => Some(text.xml10_content()),
+        Event::CData(data) => Some(Cow::Borrowed(data)),
+        Event::GeneralRef(reference) => {
+            let c = match reference.resolve_char_ref() {
+                Ok(Some(c)) => c,
+                _ => match &**reference {
+                    "amp" => '&',
+                    "lt" => '<',
+                    "gt" => '>',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "nbsp" => '\u{a0}',
+                    _ => return None,
+                },
+            };
+            Some(Cow::Owned(c.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Value of the attribute `name` (with its prefix, like `r:id`), unescaped.
+pub fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|a| {
+            a.normalized_value(XmlVersion::Implicit1_0)
+                .ok()
+                .map(Cow::into_owned)
+        })
+}
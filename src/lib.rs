@@ -1,6 +1,9 @@
 pub mod cli;
 pub mod colors;
+pub mod edit;
 pub mod errors;
+pub mod extract;
+pub mod matcher;
 pub mod output;
 pub mod schemas;
 pub mod walker;
@@ -1,24 +1,36 @@
-use regex::Regex;
+use globset::{Glob, GlobSetBuilder};
+use regex::RegexBuilder;
 use sik::{
     cli::args::Args,
+    edit::{apply_replacements, edit_results},
     errors::custom_errors::AppError,
-    output::printer::{StyledOutput, print_error, progress_bar},
-    schemas::files::FileResult,
+    extract::PathQuery,
+    matcher::Matcher,
+    output::printer::{StyledOutput, print_error, print_info, print_path, progress_bar},
+    schemas::{
+        files::FileResult,
+        options::{Preprocessor, SearchOptions},
+    },
     walker::walk,
     worker::process_file,
 };
 
 use std::{
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
     path::PathBuf,
     sync::{Arc, Mutex, mpsc},
     thread,
 };
 
 fn main() {
-    if let Err(e) = run() {
-        print_error(&e.to_string());
-        std::process::exit(1);
+    match run() {
+        Ok(()) => {}
+        // the reader went away (`sik -l foo | head -1`), there is nobody left to print to
+        Err(AppError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            print_error(&e.to_string());
+            std::process::exit(1);
+        }
     }
 }
 
@@ -42,16 +54,72 @@ fn run() -> Result<(), AppError> {
     });
 
     let path_rx = Arc::new(Mutex::new(path_rx));
+    let patterns = args
+        .patterns
+        .iter()
+        .map(|p| match args.folding {
+            Some(folding) => Matcher::folded(p, args.engine, folding),
+            None => Matcher::new(p, args.engine),
+        })
+        .collect::<Result<Vec<Matcher>, AppError>>()?;
+    let pre = match args.pre {
+        Some(command) => {
+            let mut globs = GlobSetBuilder::new();
+            for glob in &args.pre_globs {
+                globs.add(Glob::new(glob)?);
+            }
+            Some(Preprocessor {
+                command,
+                globs: globs.build()?,
+            })
+        }
+        None => None,
+    };
+    let options = SearchOptions {
+        query: args.query.clone(),
+        scope: args.scope,
+        encoding: args.encoding,
+        binary: args.binary,
+        crlf: args.crlf,
+        near: args.near,
+        record_separator: args
+            .record_separator
+            .as_deref()
+            .map(|sep| {
+                RegexBuilder::new(sep)
+                    .multi_line(true)
+                    .crlf(args.crlf)
+                    .build()
+            })
+            .transpose()?,
+        null_data: args.null_data,
+        search_zip: args.search_zip,
+        archive_depth: args.archive_depth,
+        pre,
+        documents: args.documents,
+        notebook_outputs: args.notebook_outputs,
+        strings: args.strings,
+        path_query: args
+            .path_query
+            .as_deref()
+            .map(PathQuery::parse)
+            .transpose()
+            .map_err(AppError::InvalidPattern)?,
+        path_keys: args.path_keys,
+        csv_columns: args.csv_columns,
+        sqlite: args.sqlite,
+    };
 
     for _ in 0..args.threads {
         let path_rx = Arc::clone(&path_rx);
         let result_tx = result_tx.clone();
         let prog_tx = prog_tx.clone();
 
-        let p = Regex::new(&args.pattern)?;
+        let p = patterns.clone();
+        let options = options.clone();
 
         workers.push(thread::spawn(move || -> Result<(), AppError> {
-            process_file(path_rx, p, result_tx, prog_tx)
+            process_file(path_rx, p, options, result_tx, prog_tx)
         }));
     }
 
@@ -64,15 +132,22 @@ fn run() -> Result<(), AppError> {
         total_files += 1;
     }
 
+    // the bar would end up mixed with the paths when the output is piped (`-l -0 | xargs -0`)
+    let show_progress = io::stdout().is_terminal();
+
     let mut processed = 0;
     for _ in prog_rx {
         processed += 1;
-        progress_bar(processed, total_files)?;
+        if show_progress {
+            progress_bar(processed, total_files)?;
+        }
     }
 
-    // yes, this mf cleans the line after the progress bar
-    print!("\r\x1b[2K");
-    io::stdout().flush()?;
+    if show_progress {
+        // yes, this mf cleans the line after the progress bar
+        print!("\r\x1b[2K");
+        io::stdout().flush()?;
+    }
 
     drop(result_tx);
 
@@ -80,9 +155,58 @@ fn run() -> Result<(), AppError> {
         w.join().map_err(|_| AppError::ThreadPanic)??;
     }
 
-    for r in result_rx {
-        println!("{}", StyledOutput::new(&r, type_style));
+    if args.edit_results {
+        let results: Vec<FileResult> = result_rx.into_iter().collect();
+        match edit_results(&results, args.encoding)? {
+            Some(summary) => print_info(&format!(
+                "{} line(s) written in {} file(s), {} conflict(s)",
+                summary.lines, summary.files, summary.conflicts
+            )),
+            None => print_info("no editable lines were found, the editor was not opened"),
+        }
+        return Ok(());
+    }
+
+    if args.print_options.files_with_matches {
+        for r in result_rx {
+            print_path(&r.path, args.print_options)?;
+        }
+        io::stdout().flush()?;
+        return Ok(());
     }
 
+    let Some(replacement) = args.replace else {
+        for r in result_rx {
+            println!(
+                "{}",
+                StyledOutput::new(&r, type_style).with_options(args.print_options)
+            );
+        }
+        return Ok(());
+    };
+
+    let results: Vec<FileResult> = result_rx.into_iter().collect();
+
+    if !args.confirm {
+        for r in &results {
+            println!(
+                "{}",
+                StyledOutput::new(r, type_style).with_options(args.print_options)
+            );
+        }
+    }
+
+    let summary = apply_replacements(
+        &results,
+        &patterns,
+        &replacement,
+        args.confirm,
+        args.encoding,
+    )?;
+    print_info(&format!(
+        "{} replacement(s) made in {} file(s)",
+        summary.replacements, summary.files
+    ));
+
     Ok(())
 }
@@ -0,0 +1,127 @@
+use crate::schemas::files::MatchRange;
+
+/// Finds the non-overlapping substrings of `text` within `max` edits (Levenshtein) of
+/// `pattern`, returning their byte ranges and edit distances.
+///
+/// This is Sellers' algorithm: the usual edit distance table, except a match may start at any
+/// position of the text for free. Each cell also remembers where its alignment started so the
+/// span can be highlighted. Among overlapping candidates the one with the fewest edits wins.
+pub fn find_approx(text: &str, pattern: &[char], max: usize) -> Vec<(MatchRange, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map_or(text.len(), |&(b, _)| b);
+
+    let mut found = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let Some((start, end, distance)) = best_from(&chars[pos..], pattern, max) else {
+            break;
+        };
+
+        found.push(((byte_at(pos + start), byte_at(pos + end)), distance));
+        pos += end.max(start + 1);
+    }
+
+    found
+}
+
+/// Best approximate match in `chars`, as char indices `(start, end)` plus its distance. The
+/// first end position within budget opens a candidate, and every end it could still reach
+/// (`pattern.len() + max` chars from its start) is weighed: an overlapping span with fewer
+/// edits replaces it, and so does a longer one with as many, so a whole misspelled word is
+/// covered even when the distance rises and falls again on the way (`recie`, `reciev`,
+/// `recieve` for `receive`).
+fn best_from(
+    chars: &[(usize, char)],
+    pattern: &[char],
+    max: usize,
+) -> Option<(usize, usize, usize)> {
+    let m = pattern.len();
+
+    // column of the table for the current text position: (distance, start) per pattern prefix
+    let mut col: Vec<(usize, usize)> = (0..=m).map(|j| (j, 0)).collect();
+    let mut best: Option<(usize, usize, usize)> = None;
+
+    for (i, &(_, c)) in chars.iter().enumerate() {
+        let mut prev_diag = col[0];
+        // an alignment can start right after any text position
+        col[0] = (0, i + 1);
+
+        for j in 1..=m {
+            let cost = usize::from(pattern[j - 1] != c);
+            let sub = (prev_diag.0 + cost, prev_diag.1);
+            let del = (col[j].0 + 1, col[j].1);
+            let ins = (col[j - 1].0 + 1, col[j - 1].1);
+
+            prev_diag = col[j];
+            // fewest edits first, then the shortest span
+            col[j] = [sub, del, ins]
+                .into_iter()
+                .min_by_key(|&(d, s)| (d, std::cmp::Reverse(s)))
+                .unwrap_or(sub);
+        }
+
+        let (distance, start) = col[m];
+        let end = i + 1;
+        match best {
+            Some((best_start, _, _)) if end > best_start + m + max => return best,
+            Some((_, best_end, best_distance)) if distance <= best_distance && start < best_end => {
+                best = Some((start, end, distance));
+            }
+            Some(_) => {}
+            None if distance <= max => best = Some((start, end, distance)),
+            None => {}
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans<'a>(text: &'a str, pattern: &str, max: usize) -> Vec<(&'a str, usize)> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        find_approx(text, &pattern, max)
+            .into_iter()
+            .map(|((start, end), distance)| (&text[start..end], distance))
+            .collect()
+    }
+
+    #[test]
+    fn finds_exact_matches() {
+        assert_eq!(spans("we receive it", "receive", 2), [("receive", 0)]);
+    }
+
+    #[test]
+    fn covers_the_whole_misspelled_word() {
+        assert_eq!(spans("recieve the packet", "receive", 2), [("recieve", 2)]);
+        assert_eq!(spans("recive the packet", "receive", 2), [("recive", 1)]);
+    }
+
+    #[test]
+    fn prefers_fewer_edits_among_overlapping_spans() {
+        assert_eq!(spans("xreceive", "receive", 2), [("receive", 0)]);
+    }
+
+    #[test]
+    fn does_not_swallow_the_next_word() {
+        assert_eq!(spans("recieve it", "receive", 2), [("recieve", 2)]);
+        assert_eq!(
+            spans("receive receive", "receive", 1),
+            [("receive", 0), ("receive", 0)]
+        );
+    }
+
+    #[test]
+    fn leaves_spans_over_budget_out() {
+        assert!(spans("nothing here", "receive", 2).is_empty());
+        assert!(spans("recieve", "receive", 1).is_empty());
+    }
+
+    #[test]
+    fn returns_byte_ranges() {
+        assert_eq!(spans("café cafe", "cafe", 1), [("café", 1), ("cafe", 0)]);
+    }
+}
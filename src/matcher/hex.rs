@@ -0,0 +1,114 @@
+/// A byte sequence written in hex, like `DE AD ?? EF`, where `??` stands for any byte.
+#[derive(Clone, Debug)]
+pub struct HexPattern(Vec<Option<u8>>);
+
+impl HexPattern {
+    /// Reads pairs of hex digits, the spaces between them being optional (`DEAD??EF` works too).
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let digits: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(format!("'{}' has an odd number of hex digits", pattern));
+        }
+
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| match (pair[0], pair[1]) {
+                ('?', '?') => Ok(None),
+                (high, low) => match (high.to_digit(16), low.to_digit(16)) {
+                    (Some(high), Some(low)) => Ok(Some((high * 16 + low) as u8)),
+                    _ => Err(format!("'{}{}' is not a hex byte or '??'", high, low)),
+                },
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // a pattern of wildcards alone would match at every offset
+        if bytes.iter().all(Option::is_none) {
+            return Err(format!(
+                "'{}' needs at least one byte that is not '??'",
+                pattern
+            ));
+        }
+
+        Ok(HexPattern(bytes))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Offset of every non-overlapping occurrence in `bytes`, from left to right.
+    pub fn find_iter(&self, bytes: &[u8]) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut i = 0;
+
+        while i + self.0.len() <= bytes.len() {
+            let matches = self
+                .0
+                .iter()
+                .zip(&bytes[i..])
+                .all(|(expected, b)| expected.is_none_or(|e| e == *b));
+
+            if matches {
+                found.push(i);
+                i += self.0.len();
+            } else {
+                i += 1;
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bytes_with_or_without_spaces() {
+        let spaced = HexPattern::parse("DE ad ?? 0f").unwrap();
+        let packed = HexPattern::parse("DEad??0f").unwrap();
+        assert_eq!(spaced.0, [Some(0xde), Some(0xad), None, Some(0x0f)]);
+        assert_eq!(packed.0, spaced.0);
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        assert_eq!(
+            HexPattern::parse("DE A").unwrap_err(),
+            "'DE A' has an odd number of hex digits"
+        );
+        assert_eq!(
+            HexPattern::parse("DE ?A").unwrap_err(),
+            "'?A' is not a hex byte or '??'"
+        );
+        assert_eq!(
+            HexPattern::parse("zz").unwrap_err(),
+            "'zz' is not a hex byte or '??'"
+        );
+        assert_eq!(
+            HexPattern::parse("?? ??").unwrap_err(),
+            "'?? ??' needs at least one byte that is not '??'"
+        );
+    }
+
+    #[test]
+    fn wildcards_match_any_byte() {
+        let pattern = HexPattern::parse("DE ?? EF").unwrap();
+        assert_eq!(
+            pattern.find_iter(&[0xde, 0x00, 0xef, 0xde, 0xff, 0xef, 0xde, 0xef]),
+            [0, 3]
+        );
+    }
+
+    #[test]
+    fn finds_non_overlapping_matches() {
+        let pattern = HexPattern::parse("AA AA").unwrap();
+        assert_eq!(pattern.find_iter(&[0xaa; 5]), [0, 2]);
+        assert!(pattern.find_iter(&[0xaa]).is_empty());
+    }
+}
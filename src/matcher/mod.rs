@@ -0,0 +1,9 @@
+mod fuzzy;
+mod hex;
+mod normalize;
+mod pattern;
+mod query;
+pub use hex::HexPattern;
+pub use normalize::Folding;
+pub use pattern::{Engine, Matcher};
+pub use query::{Query, Scope};
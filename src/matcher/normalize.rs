@@ -0,0 +1,123 @@
+use crate::schemas::files::MatchRange;
+use unicode_normalization::{
+    UnicodeNormalization,
+    char::{canonical_combining_class, is_combining_mark},
+};
+
+/// How text is folded before matching.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Folding {
+    /// NFC, so precomposed and decomposed accents compare equal (`--normalize`).
+    Nfc,
+    /// NFD with the combining marks dropped, so `café` matches `cafe` (`--ignore-diacritics`).
+    StripDiacritics,
+}
+
+/// Folded copy of a text that remembers where each of its bytes came from.
+pub struct Folded {
+    pub text: String,
+    /// For each byte of `text`, the start and end in the original text of the cluster (a base
+    /// character and its combining marks) it was produced from.
+    spans: Vec<(usize, usize)>,
+    original_len: usize,
+}
+
+pub fn fold(text: &str, folding: Folding) -> Folded {
+    let mut folded = Folded {
+        text: String::with_capacity(text.len()),
+        spans: Vec::with_capacity(text.len()),
+        original_len: text.len(),
+    };
+
+    // clusters are folded one by one, which keeps the mapping exact: composition never crosses
+    // from one starter to the next
+    let mut start = 0;
+    for (i, c) in text.char_indices().skip(1) {
+        if is_starter(c) {
+            folded.push_cluster(&text[start..i], start, folding);
+            start = i;
+        }
+    }
+    if start < text.len() {
+        folded.push_cluster(&text[start..], start, folding);
+    }
+
+    folded
+}
+
+/// Whether a character begins a cluster, that is NFC never composes it with the one before it.
+fn is_starter(c: char) -> bool {
+    // Hangul vowel and trailing consonant jamo are not marks, yet compose into the syllable
+    // before them
+    let hangul_jamo = matches!(c, '\u{1161}'..='\u{1175}' | '\u{11a8}'..='\u{11c2}');
+    canonical_combining_class(c) == 0 && !hangul_jamo
+}
+
+impl Folded {
+    fn push_cluster(&mut self, cluster: &str, start: usize, folding: Folding) {
+        let before = self.text.len();
+        match folding {
+            Folding::Nfc => self.text.extend(cluster.nfc()),
+            Folding::StripDiacritics => self
+                .text
+                .extend(cluster.nfd().filter(|&c| !is_combining_mark(c))),
+        }
+
+        let span = (start, start + cluster.len());
+        self.spans
+            .resize(self.spans.len() + self.text.len() - before, span);
+    }
+
+    /// Maps a range of the folded text back to the original one, widened to whole clusters.
+    pub fn original_range(&self, (start, end): MatchRange) -> MatchRange {
+        let at_start = |i: usize| self.spans.get(i).map_or(self.original_len, |s| s.0);
+
+        if start == end {
+            let pos = at_start(start);
+            return (pos, pos);
+        }
+
+        (at_start(start), self.spans[end - 1].1)
+    }
+
+    /// Maps a range of the original text to the folded one, the opposite of [`original_range`].
+    ///
+    /// [`original_range`]: Folded::original_range
+    pub fn folded_range(&self, (start, end): MatchRange) -> MatchRange {
+        let folded_start = self
+            .spans
+            .iter()
+            .position(|s| s.0 >= start)
+            .unwrap_or(self.text.len());
+        let folded_end = self
+            .spans
+            .iter()
+            .rposition(|s| s.1 <= end)
+            .map_or(folded_start, |i| (i + 1).max(folded_start));
+
+        (folded_start, folded_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composes_decomposed_accents() {
+        let text = "cafe\u{301} au lait";
+        let folded = fold(text, Folding::Nfc);
+        assert_eq!(folded.text, "café au lait");
+        assert_eq!(folded.original_range((3, 5)), (3, 6));
+    }
+
+    #[test]
+    fn composes_decomposed_hangul() {
+        let text: String = "a 한국 b".nfd().collect();
+        let folded = fold(&text, Folding::Nfc);
+        assert_eq!(folded.text, "a 한국 b");
+
+        let (start, end) = folded.original_range((2, 8));
+        assert_eq!(&text[start..end], "한국".nfd().collect::<String>());
+    }
+}
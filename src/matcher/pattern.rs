@@ -0,0 +1,205 @@
+use super::{
+    fuzzy::find_approx,
+    hex::HexPattern,
+    normalize::{Folding, fold},
+};
+use crate::{
+    errors::custom_errors::AppError,
+    schemas::files::{MatchRange, MatchResult},
+};
+use regex_automata::util::interpolate;
+
+/// Regex engine used to build the pattern.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Engine {
+    /// The `regex` crate, linear time but without lookaround or backreferences.
+    #[default]
+    Fast,
+    /// `fancy-regex`, a backtracking engine that supports `(?<=...)`, `(?!...)`, `\1`...
+    Backtracking,
+    /// Approximate matching of the pattern taken literally, within this many edits.
+    Fuzzy(usize),
+    /// The pattern is a sequence of bytes written in hex (`--hex`).
+    Hex,
+}
+
+/// A compiled pattern, hiding which engine runs it from the workers and the replacer.
+#[derive(Clone)]
+pub enum Matcher {
+    Fast(regex::Regex),
+    Backtracking(fancy_regex::Regex),
+    Fuzzy {
+        pattern: Vec<char>,
+        max: usize,
+    },
+    /// Searched in the raw bytes of the files by the workers, see [`HexPattern`].
+    Hex(HexPattern),
+    /// Runs `inner` over a folded copy of the text, mapping the matches back to the original.
+    Folded {
+        inner: Box<Matcher>,
+        folding: Folding,
+    },
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, engine: Engine) -> Result<Self, AppError> {
+        Ok(match engine {
+            Engine::Fast => Matcher::Fast(regex::Regex::new(pattern)?),
+            Engine::Backtracking => Matcher::Backtracking(fancy_regex::Regex::new(pattern)?),
+            Engine::Fuzzy(max) => {
+                let pattern: Vec<char> = pattern.chars().collect();
+                // with that many edits an empty span would already match everywhere
+                if max >= pattern.len() {
+                    return Err(AppError::InvalidPattern(format!(
+                        "--fuzzy {} allows as many edits as the pattern has characters",
+                        max
+                    )));
+                }
+                Matcher::Fuzzy { pattern, max }
+            }
+            Engine::Hex => {
+                Matcher::Hex(HexPattern::parse(pattern).map_err(AppError::InvalidPattern)?)
+            }
+        })
+    }
+
+    /// Builds the pattern after folding it the same way the searched text will be
+    /// (`--normalize`, `--ignore-diacritics`).
+    pub fn folded(pattern: &str, engine: Engine, folding: Folding) -> Result<Self, AppError> {
+        let inner = Matcher::new(&fold(pattern, folding).text, engine)?;
+        Ok(Matcher::Folded {
+            inner: Box::new(inner),
+            folding,
+        })
+    }
+
+    /// Every non-overlapping match in `text`, from left to right.
+    pub fn find_iter(&self, text: &str) -> Vec<MatchResult> {
+        let ranges: Vec<MatchRange> = match self {
+            Matcher::Fast(re) => re.find_iter(text).map(|m| (m.start(), m.end())).collect(),
+            // a runtime error (backtrack limit hit) ends the search in this line
+            Matcher::Backtracking(re) => re
+                .find_iter(text)
+                .map_while(Result::ok)
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            Matcher::Fuzzy { pattern, max } => {
+                return find_approx(text, pattern, *max)
+                    .into_iter()
+                    .map(|((start, end), distance)| {
+                        let mut m = MatchResult::new(start, end, text[start..end].to_string());
+                        m.distance = Some(distance);
+                        m
+                    })
+                    .collect();
+            }
+            // only the occurrences that don't split a character can be shown as text
+            Matcher::Hex(pattern) => pattern
+                .find_iter(text.as_bytes())
+                .into_iter()
+                .map(|start| (start, start + pattern.len()))
+                .filter(|&(start, end)| text.is_char_boundary(start) && text.is_char_boundary(end))
+                .collect(),
+            Matcher::Folded { inner, folding } => {
+                let folded = fold(text, *folding);
+                return inner
+                    .find_iter(&folded.text)
+                    .into_iter()
+                    .map(|m| {
+                        let (start, end) = folded.original_range(m.match_range);
+                        let mut original =
+                            MatchResult::new(start, end, text[start..end].to_string());
+                        original.distance = m.distance;
+                        original
+                    })
+                    .collect();
+            }
+        };
+
+        ranges
+            .into_iter()
+            .map(|(start, end)| MatchResult::new(start, end, text[start..end].to_string()))
+            .collect()
+    }
+
+    /// Appends `replacement` to `out` for the match at `range`, expanding `$1`, `${name}`...
+    /// with its capture groups. Falls back to the literal replacement when the match can't be
+    /// found again at that position.
+    pub fn expand(&self, text: &str, range: MatchRange, replacement: &str, out: &mut String) {
+        let (start, end) = range;
+        let same = |m: Option<(usize, usize)>| m == Some((start, end));
+
+        match self {
+            Matcher::Fast(re) => match re.captures_at(text, start) {
+                Some(caps) if same(caps.get(0).map(|m| (m.start(), m.end()))) => {
+                    caps.expand(replacement, out)
+                }
+                _ => out.push_str(replacement),
+            },
+            Matcher::Backtracking(re) => match re.captures_from_pos(text, start) {
+                Ok(Some(caps)) if same(caps.get(0).map(|m| (m.start(), m.end()))) => {
+                    caps.expand(replacement, out)
+                }
+                _ => out.push_str(replacement),
+            },
+            // no capture groups to expand
+            Matcher::Fuzzy { .. } | Matcher::Hex(_) => out.push_str(replacement),
+            // the groups are found in the folded text, but their content is taken from the
+            // original one, or the replacement would strip the accents of the file
+            Matcher::Folded { inner, folding } => {
+                let folded = fold(text, *folding);
+                let Some(groups) = inner.captures(&folded.text, folded.folded_range(range)) else {
+                    out.push_str(replacement);
+                    return;
+                };
+                interpolate::string(
+                    replacement,
+                    |i, out| {
+                        if let Some(Some(group)) = groups.get(i) {
+                            let (start, end) = folded.original_range(*group);
+                            out.push_str(&text[start..end]);
+                        }
+                    },
+                    |name| inner.group_index(name),
+                    out,
+                );
+            }
+        }
+    }
+
+    /// Ranges of the capture groups of the match at `range`, `None` when the match can't be
+    /// found again there or the engine has no groups.
+    fn captures(&self, text: &str, range: MatchRange) -> Option<Vec<Option<MatchRange>>> {
+        let span = |m: Option<(usize, usize)>| m.filter(|&m| m == range);
+        match self {
+            Matcher::Fast(re) => {
+                let caps = re.captures_at(text, range.0)?;
+                span(caps.get(0).map(|m| (m.start(), m.end())))?;
+                Some(
+                    caps.iter()
+                        .map(|m| m.map(|m| (m.start(), m.end())))
+                        .collect(),
+                )
+            }
+            Matcher::Backtracking(re) => {
+                let caps = re.captures_from_pos(text, range.0).ok()??;
+                span(caps.get(0).map(|m| (m.start(), m.end())))?;
+                Some(
+                    caps.iter()
+                        .map(|m| m.map(|m| (m.start(), m.end())))
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Index of the group called `name`, for `${name}` in replacements.
+    fn group_index(&self, name: &str) -> Option<usize> {
+        match self {
+            Matcher::Fast(re) => re.capture_names().position(|n| n == Some(name)),
+            Matcher::Backtracking(re) => re.capture_names().position(|n| n == Some(name)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+/// Boolean composition of several patterns, built from `-e`, `--and`, `--or`, `--not` and
+/// parentheses. Terms are indexes into the list of compiled patterns.
+#[derive(Clone, Debug)]
+pub enum Query {
+    Term(usize),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+/// Where a [`Query`] is evaluated.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Scope {
+    /// Each line on its own.
+    #[default]
+    Lines,
+    /// The file as a whole: a term is true when any line of the file matches it.
+    Files,
+}
+
+impl Query {
+    /// Evaluates the query, `term` telling whether the pattern with that index matched.
+    pub fn eval(&self, term: &impl Fn(usize) -> bool) -> bool {
+        match self {
+            Query::Term(i) => term(*i),
+            Query::And(a, b) => a.eval(term) && b.eval(term),
+            Query::Or(a, b) => a.eval(term) || b.eval(term),
+            Query::Not(q) => !q.eval(term),
+        }
+    }
+
+    /// Marks which of the `count` terms appear outside of a `--not`. Those are the ones worth
+    /// highlighting, a negated term never matches in a result.
+    pub fn positive_terms(&self, count: usize) -> Vec<bool> {
+        let mut positive = vec![false; count];
+        self.mark_positive(true, &mut positive);
+        positive
+    }
+
+    fn mark_positive(&self, positive: bool, marks: &mut [bool]) {
+        match self {
+            Query::Term(i) => marks[*i] |= positive,
+            Query::And(a, b) | Query::Or(a, b) => {
+                a.mark_positive(positive, marks);
+                b.mark_positive(positive, marks);
+            }
+            Query::Not(q) => q.mark_positive(!positive, marks),
+        }
+    }
+}
@@ -1,13 +1,17 @@
 use crate::{
-    colors::painter::{paint_blue, paint_green, paint_magenta, paint_red, paint_yellow},
+    colors::painter::{
+        Color, MATCH_COLORS, paint, paint_blue, paint_green, paint_magenta, paint_red, paint_yellow,
+    },
     errors::custom_errors::AppError,
-    schemas::files::{FileResult, MatchRange},
+    schemas::files::{FileResult, MatchRange, MatchResult, SearchResult},
 };
 
 use std::{
     fmt::{self, Display},
     io::{self, Write},
+    path::Path,
 };
+use unicode_segmentation::UnicodeSegmentation;
 
 // ----- GENERICS
 
@@ -20,6 +24,29 @@ pub enum DisplayMode {
     //Disabled,
 }
 
+/// How `--column` counts the characters before a match.
+#[derive(Clone, Copy, Default)]
+pub enum ColumnUnit {
+    /// Unicode scalar values, what a `char` is in Rust.
+    #[default]
+    Chars,
+    /// Extended grapheme clusters, closer to what the user sees on screen.
+    Graphemes,
+}
+
+/// Optional fields printed next to each matching line.
+#[derive(Clone, Copy, Default)]
+pub struct PrintOptions {
+    /// 1-based column of the first match in the line.
+    pub column: Option<ColumnUnit>,
+    /// Offset in bytes of the first match, counted from the start of the file.
+    pub byte_offset: bool,
+    /// Only print the paths of the matching files (`-l`).
+    pub files_with_matches: bool,
+    /// End file paths with a NUL byte instead of a newline (`-0`).
+    pub null: bool,
+}
+
 /// Wrapper that prints a value of type `T` using a specific [`DisplayMode`].
 ///
 /// This type does **not** define how `T` is printed by default. Instead, the caller must
@@ -34,7 +61,7 @@ pub enum DisplayMode {
 ///
 /// # Example
 ///
-/// ```rust
+/// ```rust,ignore
 /// // Assume `FileResult` implements:
 /// // impl Display for StyledOutput<'_, FileResult> { ... }
 ///
@@ -45,6 +72,7 @@ pub enum DisplayMode {
 pub struct StyledOutput<'a, T> {
     value: &'a T,
     mode: DisplayMode,
+    options: PrintOptions,
 }
 
 impl<'a, T> StyledOutput<'a, T>
@@ -57,7 +85,17 @@ where
     /// `StyledOutput<'a, T>`. This prevents constructing wrappers that cannot
     /// be formatted.
     pub fn new(value: &'a T, mode: DisplayMode) -> Self {
-        StyledOutput { value, mode }
+        StyledOutput {
+            value,
+            mode,
+            options: PrintOptions::default(),
+        }
+    }
+
+    /// Sets the optional fields (column, byte offset...) that are printed with each line.
+    pub fn with_options(mut self, options: PrintOptions) -> Self {
+        self.options = options;
+        self
     }
 }
 
@@ -65,30 +103,44 @@ where
 
 impl Display for StyledOutput<'_, FileResult> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.value.binary {
+            let file_path = self.value.path.to_str().ok_or(fmt::Error)?;
+            return writeln!(f, "binary file {} matches", paint_blue(file_path));
+        }
+
         match self.mode {
             DisplayMode::Tertiary => {
                 let file_path = self.value.path.to_str().ok_or(fmt::Error)?;
 
                 writeln!(f, "========================================")?;
-                writeln!(f, "FILE: {}", paint_blue(file_path))?;
+                write!(f, "FILE: {}{}", paint_blue(file_path), self.path_end())?;
                 writeln!(f, "========================================")?;
                 writeln!(f)?;
 
                 for r in &self.value.results {
-                    let header = format!("--> LINE {}", r.line);
+                    let lines = rows(&r.line_content).len();
+                    let mut header = match r.record {
+                        Some(record) => format!("--> RECORD {}, ", record),
+                        None => "--> ".to_string(),
+                    };
+                    if let Some(location) = &r.location {
+                        header.push_str(location);
+                    } else if lines > 1 {
+                        header.push_str(&format!("LINES {}-{}", r.line, r.line + lines - 1));
+                    } else {
+                        header.push_str(&format!("LINE {}", r.line));
+                    }
+                    if let Some(unit) = self.options.column {
+                        header.push_str(&format!(", COLUMN {}", column(r, unit)));
+                    }
+                    if self.options.byte_offset {
+                        header.push_str(&format!(", BYTE {}", byte_offset(r)));
+                    }
                     writeln!(f, "{}", paint_yellow(&header))?;
 
-                    writeln!(
-                        f,
-                        "    {}",
-                        highlight(
-                            &r.line_content,
-                            &r.matches
-                                .iter()
-                                .map(|m| m.match_range)
-                                .collect::<Vec<MatchRange>>()
-                        )
-                    )?;
+                    for text in rows(&highlight_terms(&r.line_content, &r.matches)) {
+                        writeln!(f, "    {}", text)?;
+                    }
 
                     writeln!(
                         f,
@@ -102,33 +154,28 @@ impl Display for StyledOutput<'_, FileResult> {
             DisplayMode::Secondary => {
                 write!(
                     f,
-                    "{}\n",
-                    paint_blue(&self.value.path.to_str().ok_or(fmt::Error)?)
+                    "{}{}",
+                    paint_blue(self.value.path.to_str().ok_or(fmt::Error)?),
+                    self.path_end()
                 )?;
 
-                for r in &self.value.results {
-                    let line = paint_green(&r.line.to_string());
-                    print!(
-                        "[{}] {}: {}",
-                        center_ansi(&line, 4),
-                        paint_magenta(&format!(
-                            "@({})",
-                            &r.matches
-                                .iter()
-                                .map(|m| -> String {
-                                    format!("{}-{}", m.match_range.0, m.match_range.1)
-                                })
-                                .collect::<Vec<String>>()
-                                .join(", ")
-                        )),
-                        highlight(
-                            &r.line_content,
-                            &r.matches
-                                .iter()
-                                .map(|m| -> MatchRange { m.match_range })
-                                .collect::<Vec<MatchRange>>(),
-                        )
-                    );
+                for (i, r) in self.value.results.iter().enumerate() {
+                    let ranges = paint_magenta(&format!(
+                        "@({})",
+                        &r.matches
+                            .iter()
+                            .map(|m| -> String {
+                                match m.distance {
+                                    Some(d) => {
+                                        format!("{}-{}~{}", m.match_range.0, m.match_range.1, d)
+                                    }
+                                    None => format!("{}-{}", m.match_range.0, m.match_range.1),
+                                }
+                            })
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ));
+                    self.write_result(f, i, r, &format!(" {}", ranges))?;
                 }
             }
 
@@ -136,23 +183,13 @@ impl Display for StyledOutput<'_, FileResult> {
             _ => {
                 write!(
                     f,
-                    "{}\n",
-                    paint_blue(&self.value.path.to_str().ok_or(fmt::Error)?)
+                    "{}{}",
+                    paint_blue(self.value.path.to_str().ok_or(fmt::Error)?),
+                    self.path_end()
                 )?;
 
-                for r in &self.value.results {
-                    let line = paint_green(&r.line.to_string());
-                    print!(
-                        "[{}]: {}",
-                        center_ansi(&line, 4),
-                        highlight(
-                            &r.line_content,
-                            &r.matches
-                                .iter()
-                                .map(|m| -> MatchRange { m.match_range })
-                                .collect::<Vec<MatchRange>>(),
-                        )
-                    );
+                for (i, r) in self.value.results.iter().enumerate() {
+                    self.write_result(f, i, r, "")?;
                 }
             }
         }
@@ -160,12 +197,130 @@ impl Display for StyledOutput<'_, FileResult> {
     }
 }
 
+impl StyledOutput<'_, FileResult> {
+    /// What follows a file path: a newline, or a NUL byte with `-0/--null`.
+    fn path_end(&self) -> char {
+        if self.options.null { '\0' } else { '\n' }
+    }
+
+    /// Writes the `index`th result as `[line]: text`, `extra` going right before the colon of
+    /// its first line. A result spanning several lines (a `--near` window) gets one labelled
+    /// row per line and is set apart from the previous result with `--`.
+    fn write_result(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        index: usize,
+        r: &SearchResult,
+        extra: &str,
+    ) -> fmt::Result {
+        let text = highlight_terms(&r.line_content, &r.matches);
+
+        if index > 0 && r.line_content.contains('\n') {
+            writeln!(f, "{}", paint_red("--"))?;
+        }
+
+        for (k, text) in rows(&text).into_iter().enumerate() {
+            if k == 0 {
+                let line = paint_green(&self.position(r));
+                writeln!(
+                    f,
+                    "[{}]{}{}: {}",
+                    center_ansi(&line, 4),
+                    self.offset(r),
+                    extra,
+                    text
+                )?;
+            } else {
+                let label = match r.row_labels.get(k - 1) {
+                    Some(label) => label.clone(),
+                    None => (r.line + k).to_string(),
+                };
+                writeln!(f, "[{}]: {}", center_ansi(&paint_green(&label), 4), text)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Line number, followed by the column of the first match when `--column` is set.
+    /// Records are shown as `#record:line`, and extracted documents by their location.
+    fn position(&self, r: &SearchResult) -> String {
+        let mut position = match (&r.location, r.record) {
+            (Some(location), _) => location.clone(),
+            (None, Some(record)) => format!("#{}:{}", record, r.line),
+            (None, None) => r.line.to_string(),
+        };
+        if let Some(unit) = self.options.column {
+            position.push_str(&format!(":{}", column(r, unit)));
+        }
+        position
+    }
+
+    /// Byte offset of the first match when `--byte-offset` is set, empty otherwise.
+    fn offset(&self, r: &SearchResult) -> String {
+        if self.options.byte_offset {
+            format!(" {}", paint_magenta(&format!("<{}>", byte_offset(r))))
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Splits a result in the rows it is printed on. A trailing line break (NUL-terminated records
+/// may have one) doesn't add an empty row, but an empty line still gets its own.
+fn rows(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        return vec![""];
+    }
+    text.lines().collect()
+}
+
+fn first_match_start(r: &SearchResult) -> usize {
+    r.matches.iter().map(|m| m.match_range.0).min().unwrap_or(0)
+}
+
+/// 1-based column of the first match. `match_range` is in bytes, so multi-byte characters
+/// before the match would inflate it without this.
+pub fn column(r: &SearchResult, unit: ColumnUnit) -> usize {
+    let before = &r.line_content[..first_match_start(r)];
+    let count = match unit {
+        ColumnUnit::Chars => before.chars().count(),
+        ColumnUnit::Graphemes => before.graphemes(true).count(),
+    };
+    count + 1
+}
+
+/// 0-based offset of the first match from the start of the file, like `grep -b`.
+pub fn byte_offset(r: &SearchResult) -> usize {
+    r.match_offset
+        .unwrap_or_else(|| r.byte_offset + first_match_start(r))
+}
+
 // ----------------------------------------------
 
+/// Prints just the path of a matching file, for `-l`. It is never colored, so it can be piped
+/// into `xargs` and friends. The path is written as the bytes it is made of, so names that are
+/// not valid UTF-8 still reach `xargs -0` intact.
+pub fn print_path(path: &Path, options: PrintOptions) -> io::Result<()> {
+    let end: &[u8] = if options.null { b"\0" } else { b"\n" };
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy();
+
+    let mut out = io::stdout().lock();
+    out.write_all(bytes.as_ref())?;
+    out.write_all(end)
+}
+
 pub fn print_info(message: &str) {
     println!("{} {}", paint_blue("[SIK INFO]:"), message);
 }
 
+pub fn print_warning(message: &str) {
+    eprintln!("{} {}", paint_yellow("[SIK WARN]:"), message);
+}
+
 pub fn print_error(message: &str) {
     eprintln!("{} {}", paint_red("[SIK ERROR]:"), message);
 }
@@ -220,20 +375,36 @@ pub fn progress_bar(current: usize, total: usize) -> Result<(), AppError> {
     Ok(())
 }
 
-pub fn highlight(s: &str, matches: &Vec<MatchRange>) -> String {
-    let mut matches = matches.to_owned();
-    matches.sort_by(|a, b| a.0.cmp(&b.0));
+pub fn highlight(s: &str, matches: &[MatchRange]) -> String {
+    paint_ranges(s, matches.iter().map(|&m| (m, MATCH_COLORS[0])).collect())
+}
+
+/// Like [`highlight`], but each match is painted with the color of the pattern that found it.
+pub fn highlight_terms(s: &str, matches: &[MatchResult]) -> String {
+    paint_ranges(
+        s,
+        matches
+            .iter()
+            .map(|m| (m.match_range, MATCH_COLORS[m.term % MATCH_COLORS.len()]))
+            .collect(),
+    )
+}
+
+fn paint_ranges(s: &str, mut matches: Vec<(MatchRange, Color)>) -> String {
+    matches.sort_by_key(|m| m.0.0);
 
     let mut out = String::new();
     let mut last_end = 0;
 
-    for &(start, end) in &matches {
+    for ((start, end), color) in matches {
         if start > last_end {
             out.push_str(&s[last_end..start]);
         }
 
+        // painted line by line, so a match spanning lines doesn't color what's printed between
         let word = &s[start..end];
-        out.push_str(&paint_yellow(word));
+        let painted: Vec<String> = word.split('\n').map(|w| paint(w, color)).collect();
+        out.push_str(&painted.join("\n"));
 
         last_end = end;
     }
@@ -6,12 +6,38 @@ pub type MatchRange = (usize, usize);
 pub struct FileResult {
     pub path: PathBuf,
     pub results: Vec<SearchResult>,
+    /// Set when the file was searched as binary, only a "binary file matches" notice is shown.
+    pub binary: bool,
+    /// Set when the results come from content sik produced itself (decompressed, extracted
+    /// from an archive or a document...) rather than from the file's own lines. Such files are
+    /// never rewritten.
+    pub extracted: bool,
 }
 
 #[derive(Debug)]
 pub struct SearchResult {
+    /// 1-based index of the record with `--paragraph`/`--record-separator`, `None` when the
+    /// file is searched line by line.
+    pub record: Option<usize>,
+    /// Number of the line, or of the first line of the record.
     pub line: usize,
+    /// Where the line is when a line number means nothing for it: its place in an extracted
+    /// document (`paragraph 12`, `Sheet1!B4`) or the offset of a string in a binary (`0x1f40`).
+    /// It is shown instead of the line number.
+    pub location: Option<String>,
+    /// Labels of the rows after the first one when the result spans several and line numbers
+    /// mean nothing for them, like the offsets of the rows of a hexdump.
+    pub row_labels: Vec<String>,
+    /// Offset of the first byte of the line, counted from the start of the file.
+    pub byte_offset: usize,
+    /// Offset of the first match in the file, set when the text was transcoded and the match
+    /// positions no longer count the bytes of the file.
+    pub match_offset: Option<usize>,
+    /// The line without its terminator, which is what the pattern runs against.
     pub line_content: String,
+    /// The terminator that ended the line on disk (`\n`, `\r\n` or nothing), only used when
+    /// sik writes the file back.
+    pub terminator: &'static str,
     pub matches: Vec<MatchResult>,
 }
 
@@ -19,13 +45,28 @@ pub struct SearchResult {
 pub struct MatchResult {
     pub match_range: MatchRange,
     pub content: String,
+    /// Edit distance to the pattern, only set by `--fuzzy` searches.
+    pub distance: Option<usize>,
+    /// Index of the pattern (`-e`) that produced the match.
+    pub term: usize,
 }
 
 impl SearchResult {
-    pub fn new(line: usize, line_content: String, matches: Vec<MatchResult>) -> Self {
+    pub fn new(
+        line: usize,
+        byte_offset: usize,
+        line_content: String,
+        matches: Vec<MatchResult>,
+    ) -> Self {
         SearchResult {
+            record: None,
             line,
+            location: None,
+            row_labels: Vec::new(),
+            byte_offset,
+            match_offset: None,
             line_content,
+            terminator: "",
             matches,
         }
     }
@@ -33,7 +74,19 @@ impl SearchResult {
 
 impl FileResult {
     pub fn new(path: PathBuf, results: Vec<SearchResult>) -> Self {
-        FileResult { path, results }
+        FileResult {
+            path,
+            results,
+            binary: false,
+            extracted: false,
+        }
+    }
+
+    /// Whether `--replace` and `--edit-results` can write the results back to the file. Binary
+    /// files only carry a notice and extracted content has no lines on disk, neither is ever
+    /// rewritten.
+    pub fn is_editable(&self) -> bool {
+        !self.binary && !self.extracted
     }
 }
 
@@ -42,6 +95,8 @@ impl MatchResult {
         MatchResult {
             match_range: (start, end),
             content,
+            distance: None,
+            term: 0,
         }
     }
 }
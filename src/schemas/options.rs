@@ -0,0 +1,97 @@
+use crate::{
+    extract::PathQuery,
+    matcher::{Query, Scope},
+};
+use encoding_rs::Encoding;
+use globset::GlobSet;
+use regex::Regex;
+use std::path::Path;
+
+/// What to do with files that look binary (they contain a NUL byte once decoded).
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum BinaryMode {
+    /// Leave them out of the search, without any notice.
+    #[default]
+    Skip,
+    /// Search them, but only report that they match instead of printing their lines.
+    Search,
+    /// Search and print them like any other text file.
+    Text,
+}
+
+/// How `--strings` pulls printable text out of binary files.
+#[derive(Clone, Copy)]
+pub struct StringsOptions {
+    /// Shortest run kept, in bytes of text like `strings -n`.
+    pub min_len: usize,
+    /// Also look for UTF-16LE text.
+    pub utf16: bool,
+}
+
+/// A column searched with `--csv-column`.
+#[derive(Clone)]
+pub enum CsvColumn {
+    /// Its position, counted from 0.
+    Index(usize),
+    /// The name in its header, every column with that name being searched.
+    Name(String),
+}
+
+/// External command whose output is searched instead of the file (`--pre`).
+#[derive(Clone)]
+pub struct Preprocessor {
+    pub command: String,
+    /// The files it runs on (`--pre-glob`), all of them when the set is empty.
+    pub globs: GlobSet,
+}
+
+impl Preprocessor {
+    pub fn applies_to(&self, path: &Path) -> bool {
+        self.globs.is_empty() || self.globs.is_match(path)
+    }
+}
+
+/// Settings shared by every worker, built once from the command line arguments.
+#[derive(Clone)]
+pub struct SearchOptions {
+    /// How the patterns are combined, a single pattern is just its own term.
+    pub query: Query,
+    pub scope: Scope,
+    /// Encoding forced with `-E/--encoding`. When `None` it is sniffed from the BOM and UTF-8
+    /// is assumed otherwise.
+    pub encoding: Option<&'static Encoding>,
+    pub binary: BinaryMode,
+    /// Treat `\r\n` as a line terminator instead of leaving the `\r` in the line, unless
+    /// `--no-crlf` is given.
+    pub crlf: bool,
+    /// `--near N`: every pattern must occur within N lines of the others.
+    pub near: Option<usize>,
+    /// Splits the text in records instead of lines (`--paragraph`, `--record-separator`).
+    pub record_separator: Option<Regex>,
+    /// Records end with a NUL byte instead of a newline (`--null-data`).
+    pub null_data: bool,
+    /// Search inside gzip, bzip2, xz and zstd compressed files (`-z/--search-zip`).
+    pub search_zip: bool,
+    /// How many levels of nested archives are expanded with `--search-archives`, 0 when
+    /// archives are searched as plain files.
+    pub archive_depth: usize,
+    /// `--pre`: command run on each file, its output is what gets searched.
+    pub pre: Option<Preprocessor>,
+    /// Search the text of office documents and EPUBs instead of their bytes (`--docs`).
+    pub documents: bool,
+    /// Search the text outputs of notebook cells along with their sources.
+    pub notebook_outputs: bool,
+    /// Search the printable strings of binary files instead of skipping them (`--strings`).
+    pub strings: Option<StringsOptions>,
+    /// `--path-query`: only the values at these paths of JSON, YAML and TOML files are searched,
+    /// the other files are left out.
+    pub path_query: Option<PathQuery>,
+    /// Search the keys selected by the path query instead of their values (`--path-keys`).
+    pub path_keys: bool,
+    /// `--csv-column`: only these columns of CSV and TSV files are searched, the other files
+    /// are left out. Empty when CSV files are searched as text.
+    pub csv_columns: Vec<CsvColumn>,
+    /// Search the text values of SQLite databases instead of skipping them as binary
+    /// (`--sqlite`).
+    pub sqlite: bool,
+}
@@ -0,0 +1,102 @@
+use crate::errors::custom_errors::AppError;
+use std::io::{self, Read, Seek};
+
+/// Archive formats expanded with `--search-archives`. A `.tar.gz` is a gzip stream holding a
+/// tar, it is recognized once decompressed.
+#[derive(Clone, Copy, Debug)]
+pub enum Archive {
+    /// Zip and the formats built on it (`.jar`, `.war`, `.apk`...).
+    Zip,
+    Tar,
+}
+
+impl Archive {
+    pub fn detect(bytes: &[u8]) -> Option<Archive> {
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(Archive::Zip)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(Archive::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads each regular member of the archive and hands its name and content to `f`, in the
+/// order they are stored. `limit` gives the size allowed for a member from its first block,
+/// larger members are left out, like oversized files.
+///
+/// The archive itself is read from `input` as it goes, only the members are held in memory.
+/// Errors reading the archive come back as [`AppError::Io`], the others are whatever `f`
+/// returned.
+pub fn for_each_member(
+    input: impl Read + Seek,
+    archive: Archive,
+    limit: impl Fn(&[u8]) -> u64,
+    mut f: impl FnMut(&str, Vec<u8>) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    match archive {
+        Archive::Zip => {
+            let mut zip = zip::ZipArchive::new(input).map_err(io::Error::other)?;
+
+            for i in 0..zip.len() {
+                let mut member = zip.by_index(i).map_err(io::Error::other)?;
+                if member.is_dir() {
+                    continue;
+                }
+
+                let name = member.name().to_string();
+                let size = member.size();
+                if let Some(content) = read_member(&mut member, size, &limit)? {
+                    f(&name, content)?;
+                }
+            }
+            Ok(())
+        }
+        Archive::Tar => for_each_tar_member(input, limit, f),
+    }
+}
+
+/// [`for_each_member`] for a tar, which is read front to back and so can come straight out of
+/// a decompressor.
+pub fn for_each_tar_member(
+    input: impl Read,
+    limit: impl Fn(&[u8]) -> u64,
+    mut f: impl FnMut(&str, Vec<u8>) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    let mut tar = tar::Archive::new(input);
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let size = entry.size();
+        if let Some(content) = read_member(&mut entry, size, &limit)? {
+            f(&name, content)?;
+        }
+    }
+
+    Ok(())
+}
+
+// the size in the headers is not trusted, a member is never read past the limit
+fn read_member(
+    member: &mut impl Read,
+    size: u64,
+    limit: impl Fn(&[u8]) -> u64,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut content = Vec::new();
+    member.by_ref().take(512).read_to_end(&mut content)?;
+    let limit = limit(&content);
+    if size > limit {
+        return Ok(None);
+    }
+
+    member
+        .take(limit + 1 - content.len() as u64)
+        .read_to_end(&mut content)?;
+    Ok((content.len() as u64 <= limit).then_some(content))
+}
@@ -0,0 +1,333 @@
+use super::worker::merge_matches;
+use crate::{
+    matcher::{Matcher, Scope},
+    schemas::{
+        files::{MatchResult, SearchResult},
+        options::{CsvColumn, SearchOptions},
+    },
+};
+use std::path::Path;
+
+/// Field delimiter of the files `--csv-column` reads, from their extension.
+pub fn delimiter(path: &Path) -> Option<u8> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "csv" => Some(b','),
+        "tsv" | "tab" => Some(b'\t'),
+        _ => None,
+    }
+}
+
+/// A field as read, and where it is written in the text, quotes included.
+struct Field {
+    value: String,
+    start: usize,
+    end: usize,
+}
+
+struct Record {
+    line: usize,
+    start: usize,
+    end: usize,
+    fields: Vec<Field>,
+}
+
+/// Splits `text` in records the way RFC 4180 does: fields between double quotes can hold the
+/// delimiter and newlines, and `""` stands for a quote in them. Blank lines are left out.
+fn parse(text: &str, delimiter: u8) -> Vec<Record> {
+    let bytes = text.as_bytes();
+    let mut records = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (start, first_line) = (i, line);
+        let mut fields = Vec::new();
+
+        loop {
+            let field_start = i;
+            let mut value = String::new();
+
+            if bytes.get(i) == Some(&b'"') {
+                i += 1;
+                let mut chunk = i;
+                loop {
+                    match bytes.get(i) {
+                        Some(b'"') if bytes.get(i + 1) == Some(&b'"') => {
+                            value.push_str(&text[chunk..=i]);
+                            i += 2;
+                            chunk = i;
+                        }
+                        Some(b'"') => {
+                            value.push_str(&text[chunk..i]);
+                            i += 1;
+                            break;
+                        }
+                        Some(b) => {
+                            if *b == b'\n' {
+                                line += 1;
+                            }
+                            i += 1;
+                        }
+                        // an unclosed quote runs to the end of the file
+                        None => {
+                            value.push_str(&text[chunk..i]);
+                            break;
+                        }
+                    }
+                }
+            }
+
+            // text after a closing quote is kept as is, like spreadsheets do
+            let rest = i;
+            while bytes.get(i).is_some_and(|&b| b != delimiter && b != b'\n') {
+                i += 1;
+            }
+            let mut end = i;
+            if end > rest && bytes[end - 1] == b'\r' && bytes.get(i) != Some(&delimiter) {
+                end -= 1;
+            }
+            value.push_str(&text[rest..end]);
+            fields.push(Field {
+                value,
+                start: field_start,
+                end,
+            });
+
+            if bytes.get(i) == Some(&delimiter) {
+                i += 1;
+                continue;
+            }
+            break;
+        }
+
+        let end = fields.last().map_or(i, |f| f.end);
+        if bytes.get(i) == Some(&b'\n') {
+            line += 1;
+            i += 1;
+        }
+
+        let blank = fields.len() == 1 && fields[0].start == fields[0].end;
+        if !blank {
+            records.push(Record {
+                line: first_line,
+                start,
+                end,
+                fields,
+            });
+        }
+    }
+
+    records
+}
+
+/// Runs the patterns over the cells of `columns` in each record after the header, the whole
+/// record being reported with the matching cells highlighted and labelled `record 12, email`.
+/// Returns `None` when none of the columns is in the file, or when a `--files` query is false
+/// for it.
+pub fn search_csv(
+    text: &str,
+    base_offset: usize,
+    delimiter: u8,
+    patterns: &[Matcher],
+    positive: &[bool],
+    options: &SearchOptions,
+) -> Option<Vec<SearchResult>> {
+    let records = parse(text, delimiter);
+    let (header, rows) = records.split_first()?;
+
+    // a name can head several columns, each of them is searched
+    let mut columns = Vec::new();
+    for column in &options.csv_columns {
+        match column {
+            CsvColumn::Index(index) => {
+                let name = header
+                    .fields
+                    .get(*index)
+                    .map_or_else(|| format!("column {}", index + 1), |f| f.value.clone());
+                columns.push((*index, name));
+            }
+            CsvColumn::Name(name) => columns.extend(
+                header
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| f.value.trim() == name)
+                    .map(|(index, f)| (index, f.value.clone())),
+            ),
+        }
+    }
+    if columns.is_empty() {
+        return None;
+    }
+
+    let mut results = Vec::new();
+    let mut seen = vec![false; patterns.len()];
+
+    for (number, record) in rows.iter().enumerate() {
+        let mut matches = Vec::new();
+        let mut names = Vec::new();
+        let mut hit = vec![false; patterns.len()];
+
+        for (index, name) in &columns {
+            let Some(field) = record.fields.get(*index) else {
+                continue;
+            };
+
+            let mut term_shown = None;
+            for (term, pattern) in patterns.iter().enumerate() {
+                if pattern.find_iter(&field.value).is_empty() {
+                    continue;
+                }
+                hit[term] = true;
+                seen[term] = true;
+                if positive[term] {
+                    term_shown.get_or_insert(term);
+                }
+            }
+
+            // the whole cell is highlighted, in the color of the first term found in it
+            if let Some(term) = term_shown {
+                let mut m = MatchResult::new(
+                    field.start - record.start,
+                    field.end - record.start,
+                    text[field.start..field.end].to_string(),
+                );
+                m.term = term;
+                matches.push(m);
+                names.push(name.as_str());
+            }
+        }
+
+        let qualifies = match options.scope {
+            Scope::Lines => options.query.eval(&|term| hit[term]),
+            Scope::Files => !matches.is_empty(),
+        };
+        if !qualifies {
+            continue;
+        }
+
+        let mut result = SearchResult::new(
+            record.line,
+            base_offset + record.start,
+            text[record.start..record.end].to_string(),
+            merge_matches(matches),
+        );
+        result.location = Some(if names.is_empty() {
+            format!("record {}", number + 1)
+        } else {
+            format!("record {}, {}", number + 1, names.join(", "))
+        });
+        results.push(result);
+    }
+
+    if options.scope == Scope::Files && !options.query.eval(&|term| seen[term]) {
+        return None;
+    }
+
+    Some(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::{Engine, Query};
+
+    fn values(text: &str, delimiter: u8) -> Vec<Vec<String>> {
+        parse(text, delimiter)
+            .into_iter()
+            .map(|r| r.fields.into_iter().map(|f| f.value).collect())
+            .collect()
+    }
+
+    fn options(columns: Vec<CsvColumn>) -> SearchOptions {
+        SearchOptions {
+            query: Query::Term(0),
+            scope: Scope::Lines,
+            encoding: None,
+            binary: Default::default(),
+            crlf: true,
+            near: None,
+            record_separator: None,
+            null_data: false,
+            search_zip: false,
+            archive_depth: 0,
+            pre: None,
+            documents: false,
+            notebook_outputs: false,
+            strings: None,
+            path_query: None,
+            path_keys: false,
+            csv_columns: columns,
+            sqlite: false,
+        }
+    }
+
+    fn search(text: &str, column: CsvColumn, pattern: &str) -> Option<Vec<SearchResult>> {
+        let patterns = [Matcher::new(pattern, Engine::Fast).unwrap()];
+        search_csv(text, 0, b',', &patterns, &[true], &options(vec![column]))
+    }
+
+    #[test]
+    fn keeps_delimiters_in_quotes() {
+        assert_eq!(values("a,\"b,c\",d\n", b','), [["a", "b,c", "d"]]);
+        assert_eq!(values("a\t\"b\tc\"\n", b'\t'), [["a", "b\tc"]]);
+    }
+
+    #[test]
+    fn unescapes_doubled_quotes() {
+        assert_eq!(
+            values("\"say \"\"hi\"\"\",x\n", b','),
+            [["say \"hi\"", "x"]]
+        );
+    }
+
+    #[test]
+    fn strips_crlf() {
+        assert_eq!(values("a,b\r\n\"c\",d\r\n", b','), [["a", "b"], ["c", "d"]]);
+    }
+
+    #[test]
+    fn reads_multiline_fields() {
+        let records = parse("id,note\n1,\"two\nlines\"\n2,x\n", b',');
+        assert_eq!(records[1].fields[1].value, "two\nlines");
+        assert_eq!(records[1].line, 2);
+        assert_eq!(records[2].line, 4);
+        assert_eq!(records[2].start, "id,note\n1,\"two\nlines\"\n".len());
+    }
+
+    #[test]
+    fn skips_blank_lines() {
+        assert_eq!(values("a\n\nb\n", b','), [["a"], ["b"]]);
+    }
+
+    #[test]
+    fn labels_matches_with_the_column() {
+        let text = "name,email\nann,ann@example.com\nbob,bob@test.org\n";
+        let results = search(text, CsvColumn::Name("email".into()), "example").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line, 2);
+        assert_eq!(results[0].location.as_deref(), Some("record 1, email"));
+        assert_eq!(results[0].matches[0].content, "ann@example.com");
+    }
+
+    #[test]
+    fn skips_records_missing_the_column() {
+        let text = "name,email\nann\nbob,bob@example.com\n";
+        let results = search(text, CsvColumn::Index(1), "example").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line, 3);
+    }
+
+    #[test]
+    fn leaves_out_files_without_the_column() {
+        assert!(search("a,b\n1,2\n", CsvColumn::Name("c".into()), "1").is_none());
+    }
+
+    #[test]
+    fn finds_numeric_headers_by_name() {
+        let text = "region,2020\nnorth,42\n";
+        let results = search(text, CsvColumn::Name("2020".into()), "42").unwrap();
+        assert_eq!(results[0].location.as_deref(), Some("record 1, 2020"));
+    }
+}
@@ -0,0 +1,62 @@
+use encoding_rs::{Decoder, Encoding, UTF_8};
+use std::borrow::Cow;
+
+/// Turns the raw bytes of a file into text.
+///
+/// A forced encoding wins over everything else (a BOM of that same encoding is still
+/// stripped). Otherwise a UTF-8, UTF-16LE or UTF-16BE BOM picks the encoding, and files
+/// without one are read as UTF-8, replacing invalid sequences.
+///
+/// Along with the text, the length of the BOM that was dropped is returned, so offsets in the
+/// text can be turned back into file offsets.
+pub fn decode<'a>(bytes: &'a [u8], forced: Option<&'static Encoding>) -> (Cow<'a, str>, usize) {
+    let (encoding, bom_len) = detect(bytes, forced);
+    let text = encoding.decode_without_bom_handling(&bytes[bom_len..]).0;
+    (text, bom_len)
+}
+
+/// The encoding `decode` reads `bytes` with, and the length of the BOM it drops.
+pub fn detect(bytes: &[u8], forced: Option<&'static Encoding>) -> (&'static Encoding, usize) {
+    match forced {
+        Some(encoding) => match Encoding::for_bom(bytes) {
+            Some((bom_encoding, bom_len)) if bom_encoding == encoding => (encoding, bom_len),
+            _ => (encoding, 0),
+        },
+        None => Encoding::for_bom(bytes).unwrap_or((UTF_8, 0)),
+    }
+}
+
+/// Finds where the characters of a transcoded text come from in the file, by decoding it
+/// again one byte at a time. Offsets in text that was not transcoded are the file's own, once
+/// the BOM is added to them.
+pub struct SourceOffsets<'a> {
+    bytes: &'a [u8],
+    decoder: Decoder,
+    read: usize,
+    written: usize,
+}
+
+impl<'a> SourceOffsets<'a> {
+    pub fn new(bytes: &'a [u8], forced: Option<&'static Encoding>) -> Self {
+        let (encoding, bom_len) = detect(bytes, forced);
+        SourceOffsets {
+            bytes,
+            decoder: encoding.new_decoder_without_bom_handling(),
+            read: bom_len,
+            written: 0,
+        }
+    }
+
+    /// File offset of the character at `offset` in the text. Offsets must be asked for in
+    /// increasing order, the bytes are only walked once.
+    pub fn get(&mut self, offset: usize) -> usize {
+        let mut out = [0; 32];
+        while self.written < offset && self.read < self.bytes.len() {
+            let input = &self.bytes[self.read..self.read + 1];
+            let (_, read, written, _) = self.decoder.decode_to_utf8(input, &mut out, false);
+            self.read += read;
+            self.written += written;
+        }
+        self.read
+    }
+}
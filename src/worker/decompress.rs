@@ -0,0 +1,137 @@
+use std::io::{self, Read, Write};
+
+/// Compression formats searched through with `-z/--search-zip`.
+#[derive(Clone, Copy, Debug)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Recognizes the format from the magic bytes at the start of the file, the extension is
+    /// not looked at since rotated logs are often renamed (`app.log.1.gz`, `app.log-2024`).
+    pub fn detect(bytes: &[u8]) -> Option<Compression> {
+        // gzip only ever uses deflate, method 8
+        if bytes.starts_with(&[0x1f, 0x8b, 0x08]) {
+            Some(Compression::Gzip)
+        } else if is_bzip2(bytes) {
+            Some(Compression::Bzip2)
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// `BZh` is common enough at the start of text, bzip2 follows it with the block size and the
+/// magic of the first block, or of the end of the stream when it is empty.
+fn is_bzip2(bytes: &[u8]) -> bool {
+    const BLOCK: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+    const END: [u8; 6] = [0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+
+    bytes.len() >= 10
+        && bytes.starts_with(b"BZh")
+        && (b'1'..=b'9').contains(&bytes[3])
+        && (bytes[4..10] == BLOCK || bytes[4..10] == END)
+}
+
+/// Collects the decompressed bytes, failing as soon as they grow past `limit` so a small bomb
+/// never gets fully inflated in memory. What fits in the limit is kept.
+struct Limited {
+    bytes: Vec<u8>,
+    limit: usize,
+    exceeded: bool,
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let room = self.limit - self.bytes.len();
+        if buf.len() > room {
+            self.bytes.extend_from_slice(&buf[..room]);
+            self.exceeded = true;
+            return Err(io::Error::other("decompressed size limit exceeded"));
+        }
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Streams `bytes` through the decoder of `compression`. Returns `Ok(None)` when the output is
+/// larger than `limit`, the same way oversized files are left out of the search.
+pub fn decompress(
+    bytes: &[u8],
+    compression: Compression,
+    limit: usize,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut out = Limited {
+        bytes: Vec::new(),
+        limit,
+        exceeded: false,
+    };
+
+    match decode(bytes, compression, &mut out) {
+        Ok(()) => Ok(Some(out.bytes)),
+        Err(_) if out.exceeded => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The first `len` bytes `input` decompresses to, enough to tell a tarball by its header
+/// without inflating the rest. Only as much of `input` as the decoder needs is read, a stream
+/// that turns out corrupt gives back what was decoded before the error.
+pub fn peek(input: impl Read, compression: Compression, len: usize) -> Vec<u8> {
+    let mut out = Limited {
+        bytes: Vec::with_capacity(len),
+        limit: len,
+        exceeded: false,
+    };
+    let _ = decode(input, compression, &mut out);
+    out.bytes
+}
+
+/// A reader over what `input` decompresses to, so a tarball can be walked without inflating it
+/// whole. The xz decoder only writes its output out, an xz stream is decompressed in memory
+/// first, failing past `limit` bytes.
+pub fn reader<'a>(
+    input: impl Read + 'a,
+    compression: Compression,
+    limit: usize,
+) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(input)),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(input)),
+        Compression::Xz => {
+            let mut out = Limited {
+                bytes: Vec::new(),
+                limit,
+                exceeded: false,
+            };
+            decode(input, compression, &mut out)?;
+            Box::new(io::Cursor::new(out.bytes))
+        }
+        Compression::Zstd => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(input)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+        ),
+    })
+}
+
+fn decode(input: impl Read, compression: Compression, out: &mut Limited) -> io::Result<()> {
+    match compression {
+        // the xz decoder only writes out a full dictionary at a time, a peek decodes that much
+        Compression::Xz => lzma_rs::xz_decompress(&mut io::BufReader::new(input), out)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+        // the multi-member decoders keep going after the first member, like `zcat` does with
+        // concatenated archives
+        _ => io::copy(&mut reader(input, compression, out.limit)?, out).map(|_| ()),
+    }
+}
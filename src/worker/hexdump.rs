@@ -0,0 +1,64 @@
+use crate::schemas::files::{MatchResult, SearchResult};
+
+/// Bytes shown on each row of the dump.
+const ROW: usize = 16;
+
+/// Builds the result of a byte match at `offset`: the rows of a hexdump holding it, like
+/// `00001f40  de ad be ef ...  |....|`, with the matched bytes highlighted on both sides. A
+/// match crossing the end of a row is shown on as many rows, one per line, the rows after the
+/// first labelled with their own offset.
+pub fn dump_match(bytes: &[u8], offset: usize, len: usize) -> SearchResult {
+    let start = offset / ROW * ROW;
+    let end = ((offset + len).div_ceil(ROW) * ROW).min(bytes.len());
+
+    let mut lines = Vec::new();
+    let mut matches = Vec::new();
+    // where the current row starts in the text of the result
+    let mut at = 0;
+
+    for (i, row) in bytes[start..end].chunks(ROW).enumerate() {
+        let row_start = start + i * ROW;
+        let hex = row
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii: String = row
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    char::from(b)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        // the last row can be short, its text column still lines up with the others
+        let width = ROW * 3 - 1;
+        let line = format!("{:08x}  {:<width$}  |{}|", row_start, hex, ascii);
+
+        // the part of the match on this row
+        let first = offset.max(row_start) - row_start;
+        let last = (offset + len).min(row_start + row.len()) - row_start;
+        if first < last {
+            let hex_range = (at + 10 + first * 3, at + 10 + last * 3 - 1);
+            let ascii_range = (at + 10 + width + 3 + first, at + 10 + width + 3 + last);
+            for (s, e) in [hex_range, ascii_range] {
+                matches.push(MatchResult::new(s, e, line[s - at..e - at].to_string()));
+            }
+        }
+
+        at += line.len() + 1;
+        lines.push(line);
+    }
+
+    let row_labels = (start + ROW..end)
+        .step_by(ROW)
+        .map(|row| format!("{:#x}", row))
+        .collect();
+    let mut result = SearchResult::new(start / ROW + 1, start, lines.join("\n"), matches);
+    result.location = Some(format!("{:#x}", offset));
+    result.row_labels = row_labels;
+    result
+}
@@ -0,0 +1,203 @@
+use regex::Regex;
+
+/// A piece of text the patterns run against: a line, or a whole record with `--paragraph` and
+/// `--record-separator`.
+pub struct Chunk<'a> {
+    /// 1-based index of the record, `None` when the text is split in lines.
+    pub record: Option<usize>,
+    /// 1-based number of the (first) line.
+    pub line: usize,
+    /// Offset of the chunk from the start of the text.
+    pub offset: usize,
+    pub text: &'a str,
+    pub terminator: &'static str,
+}
+
+/// Splits `text` into lines, yielding each one without its terminator alongside the terminator
+/// that ended it (empty for a last line without one).
+///
+/// `\n` always ends a line. With `crlf` a `\r` right before it is part of the terminator too,
+/// so `$` matches before it and it never reaches the printers.
+pub fn split_lines(text: &str, crlf: bool) -> impl Iterator<Item = (&str, &'static str)> {
+    text.split_inclusive('\n')
+        .map(move |line| match line.strip_suffix('\n') {
+            Some(content) if crlf && content.ends_with('\r') => {
+                (&content[..content.len() - 1], "\r\n")
+            }
+            Some(content) => (content, "\n"),
+            None => (line, ""),
+        })
+}
+
+/// Splits `text` into the chunks the patterns run against: lines, NUL-terminated records with
+/// `null_data`, or records delimited by `separator` when one is given.
+pub fn split_chunks<'a>(
+    text: &'a str,
+    crlf: bool,
+    null_data: bool,
+    separator: Option<&Regex>,
+) -> Vec<Chunk<'a>> {
+    if null_data {
+        return split_nul(text);
+    }
+
+    let Some(separator) = separator else {
+        let mut offset = 0;
+        return split_lines(text, crlf)
+            .enumerate()
+            .map(|(i, (line, terminator))| {
+                let chunk = Chunk {
+                    record: None,
+                    line: i + 1,
+                    offset,
+                    text: line,
+                    terminator,
+                };
+                offset += line.len() + terminator.len();
+                chunk
+            })
+            .collect();
+    };
+
+    let mut chunks = Vec::new();
+    let mut line = 1;
+    let mut last = 0;
+
+    let bounds = separator
+        .find_iter(text)
+        .map(|m| (m.start(), m.end()))
+        .chain(std::iter::once((text.len(), text.len())));
+
+    for (start, end) in bounds {
+        let raw = &text[last..start];
+
+        // the line breaks around a separator belong to it, not to the records
+        let trimmed_start = raw.trim_start_matches(['\r', '\n']);
+        let skipped = &raw[..raw.len() - trimmed_start.len()];
+        let record = trimmed_start.trim_end_matches(['\r', '\n']);
+
+        if !record.is_empty() {
+            chunks.push(Chunk {
+                record: Some(chunks.len() + 1),
+                line: line + skipped.matches('\n').count(),
+                offset: last + skipped.len(),
+                text: record,
+                terminator: "",
+            });
+        }
+
+        line += text[last..end].matches('\n').count();
+        last = end;
+    }
+
+    chunks
+}
+
+/// Records ended by a NUL byte, like the output of `find -print0`. They are taken verbatim, the
+/// line breaks inside them included.
+fn split_nul(text: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut line = 1;
+    let mut offset = 0;
+
+    for (i, raw) in text.split_inclusive('\0').enumerate() {
+        let (record, terminator) = match raw.strip_suffix('\0') {
+            Some(record) => (record, "\0"),
+            None => (raw, ""),
+        };
+
+        chunks.push(Chunk {
+            record: Some(i + 1),
+            line,
+            offset,
+            text: record,
+            terminator,
+        });
+
+        line += raw.matches('\n').count();
+        offset += raw.len();
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::RegexBuilder;
+
+    /// Each chunk as `(record, line, offset, text, terminator)`.
+    fn chunks<'a>(
+        text: &'a str,
+        crlf: bool,
+        null_data: bool,
+        separator: Option<&str>,
+    ) -> Vec<(Option<usize>, usize, usize, &'a str, &'static str)> {
+        let separator = separator.map(|sep| {
+            RegexBuilder::new(sep)
+                .multi_line(true)
+                .crlf(crlf)
+                .build()
+                .unwrap()
+        });
+        split_chunks(text, crlf, null_data, separator.as_ref())
+            .into_iter()
+            .map(|c| (c.record, c.line, c.offset, c.text, c.terminator))
+            .collect()
+    }
+
+    const PARAGRAPH: &str = r"\r?\n(?:[ \t]*\r?\n)+";
+
+    #[test]
+    fn splits_lines_with_their_terminators() {
+        assert_eq!(
+            chunks("a\r\nb\nc", true, false, None),
+            [
+                (None, 1, 0, "a", "\r\n"),
+                (None, 2, 3, "b", "\n"),
+                (None, 3, 5, "c", ""),
+            ]
+        );
+        assert_eq!(
+            chunks("a\r\nb", false, false, None),
+            [(None, 1, 0, "a\r", "\n"), (None, 2, 3, "b", "")]
+        );
+    }
+
+    #[test]
+    fn splits_paragraphs() {
+        assert_eq!(
+            chunks("a\nb\n\n  \nc\n", true, false, Some(PARAGRAPH)),
+            [(Some(1), 1, 0, "a\nb", ""), (Some(2), 5, 8, "c", ""),]
+        );
+    }
+
+    #[test]
+    fn splits_crlf_paragraphs() {
+        assert_eq!(
+            chunks("a\r\nb\r\n\r\nc\r\n", true, false, Some(PARAGRAPH)),
+            [(Some(1), 1, 0, "a\r\nb", ""), (Some(2), 4, 8, "c", ""),]
+        );
+    }
+
+    #[test]
+    fn splits_on_a_separator_regex() {
+        let text = "---\r\nx: 1\r\n---\r\ny: 2\r\n";
+        assert_eq!(
+            chunks(text, true, false, Some("^---$")),
+            [(Some(1), 2, 5, "x: 1", ""), (Some(2), 4, 16, "y: 2", ""),]
+        );
+    }
+
+    #[test]
+    fn splits_nul_terminated_records() {
+        assert_eq!(
+            chunks("a\nb\0c\0d", true, true, None),
+            [
+                (Some(1), 1, 0, "a\nb", "\0"),
+                (Some(2), 2, 4, "c", "\0"),
+                (Some(3), 2, 6, "d", ""),
+            ]
+        );
+    }
+}
@@ -1,2 +1,15 @@
+mod archive;
+mod csv;
+mod decode;
+mod decompress;
+mod hexdump;
+mod lines;
+mod near;
+mod preprocess;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod strings;
+#[allow(clippy::module_inception)]
 mod worker;
+pub(crate) use decode::detect as detect_encoding;
 pub use worker::process_file;
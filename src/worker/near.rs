@@ -0,0 +1,100 @@
+use super::{lines::split_lines, worker::merge_matches};
+use crate::{
+    matcher::Matcher,
+    schemas::files::{MatchResult, SearchResult},
+};
+use std::collections::VecDeque;
+
+/// A line kept in the sliding window, with the matches of every pattern found in it.
+struct WindowLine<'a> {
+    number: usize,
+    offset: usize,
+    text: &'a str,
+    matches: Vec<MatchResult>,
+}
+
+/// Reports the places where every pattern occurs within `distance` lines of each other.
+///
+/// The last `distance + 1` lines are kept in a window. When a line completes the set of
+/// patterns, the window from its first matching line up to this one becomes a single result,
+/// its lines joined with `\n` and the match ranges shifted to fit. The window is then emptied,
+/// so results never overlap.
+pub fn search_near(
+    text: &str,
+    base_offset: usize,
+    patterns: &[Matcher],
+    distance: usize,
+    crlf: bool,
+) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+    let mut window: VecDeque<WindowLine> = VecDeque::new();
+    let mut offset = base_offset;
+
+    for (i, (line, terminator)) in split_lines(text, crlf).enumerate() {
+        let mut matches = Vec::new();
+        for (term, pattern) in patterns.iter().enumerate() {
+            matches.extend(pattern.find_iter(line).into_iter().map(|mut m| {
+                m.term = term;
+                m
+            }));
+        }
+
+        let hit = !matches.is_empty();
+        window.push_back(WindowLine {
+            number: i + 1,
+            offset,
+            text: line,
+            matches,
+        });
+        offset += line.len() + terminator.len();
+
+        while window.front().is_some_and(|l| l.number + distance < i + 1) {
+            window.pop_front();
+        }
+
+        if !hit || !covers_all(&window, patterns.len()) {
+            continue;
+        }
+
+        // the window starts at its first line with a match
+        while window.front().is_some_and(|l| l.matches.is_empty()) {
+            window.pop_front();
+        }
+
+        results.push(join_window(window.drain(..)));
+    }
+
+    results
+}
+
+fn covers_all(window: &VecDeque<WindowLine>, count: usize) -> bool {
+    let mut seen = vec![false; count];
+    for m in window.iter().flat_map(|l| &l.matches) {
+        seen[m.term] = true;
+    }
+    seen.into_iter().all(|s| s)
+}
+
+fn join_window<'a>(lines: impl Iterator<Item = WindowLine<'a>>) -> SearchResult {
+    let mut content = String::new();
+    let mut matches = Vec::new();
+    let mut first: Option<(usize, usize)> = None;
+
+    for line in lines {
+        if first.is_none() {
+            first = Some((line.number, line.offset));
+        } else {
+            content.push('\n');
+        }
+
+        let shift = content.len();
+        for mut m in line.matches {
+            m.match_range = (m.match_range.0 + shift, m.match_range.1 + shift);
+            matches.push(m);
+        }
+        content.push_str(line.text);
+    }
+
+    let (number, offset) = first.unwrap_or((0, 0));
+    SearchResult::new(number, offset, content, merge_matches(matches))
+}
@@ -0,0 +1,24 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Runs `command path` and returns what it printed on stdout. When the command cannot be
+/// started or fails, the error explains why, with the first line it printed on stderr.
+pub fn preprocess(command: &str, path: &Path) -> Result<Vec<u8>, String> {
+    let output = Command::new(command)
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("cannot run {}: {}", command, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().find(|l| !l.trim().is_empty()) {
+            Some(reason) => format!("{} failed ({}): {}", command, output.status, reason.trim()),
+            None => format!("{} failed ({})", command, output.status),
+        });
+    }
+
+    Ok(output.stdout)
+}
@@ -0,0 +1,137 @@
+use crate::output::printer::print_warning;
+use rusqlite::{Connection, OpenFlags, types::ValueRef};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Databases are told apart from other `.db` files (`Thumbs.db`, Berkeley DB...) by the header
+/// every SQLite file starts with.
+pub fn is_database(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    if !matches!(extension.as_deref(), Some("sqlite" | "sqlite3" | "db")) {
+        return false;
+    }
+
+    let mut header = [0; 16];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| &header == b"SQLite format 3\0")
+}
+
+/// Opens the database read-only and hands every text value of its tables to `f`, along with
+/// the table, the row and the column it is stored in. Numbers and blobs are left out. Rows are
+/// named by their rowid, or in tables created `WITHOUT ROWID` by their primary key, its values
+/// joined with commas. A table that cannot be read is skipped with a warning.
+pub fn for_each_text(
+    path: &Path,
+    mut f: impl FnMut(&str, &str, &str, &str),
+) -> rusqlite::Result<()> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let db = if wal_mode(path) && !wal_path(path).exists() {
+        // a reader would create the -wal and -shm files next to the database and leave them
+        // there, with nothing pending in the WAL the file can be read as is
+        Connection::open_with_flags(immutable_uri(path), flags | OpenFlags::SQLITE_OPEN_URI)?
+    } else {
+        Connection::open_with_flags(path, flags)?
+    };
+
+    let tables = db
+        .prepare(
+            "SELECT name, wr FROM pragma_table_list WHERE schema = 'main' \
+             AND type IN ('table', 'virtual') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (table, without_rowid) in tables {
+        let key = if without_rowid {
+            db.prepare("SELECT name FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")?
+                .query_map([&table], |row| row.get::<_, String>(0))?
+                .map(|name| name.map(|name| quote(&name)))
+                .collect::<rusqlite::Result<Vec<_>>>()?
+        } else {
+            vec!["rowid".to_string()]
+        };
+        let query = format!("SELECT {}, * FROM {}", key.join(", "), quote(&table));
+        let mut statement = match db.prepare(&query) {
+            Ok(statement) => statement,
+            Err(e) => {
+                print_warning(&format!(
+                    "skipping table {} of {}: {}",
+                    table,
+                    path.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let id = (0..key.len())
+                .map(|i| row.get_ref(i).map(value_text))
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .join(",");
+            for (i, column) in columns.iter().enumerate().skip(key.len()) {
+                if let ValueRef::Text(text) = row.get_ref(i)? {
+                    f(&table, &id, column, &String::from_utf8_lossy(text));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A key value as it is shown in the location of a row.
+fn value_text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(r) => r.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+        ValueRef::Blob(blob) => blob.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
+
+/// Bytes 18 and 19 of the header are 2 in databases using write-ahead logging.
+fn wal_mode(path: &Path) -> bool {
+    let mut header = [0; 20];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| header[18] == 2 && header[19] == 2)
+}
+
+fn wal_path(path: &Path) -> PathBuf {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    PathBuf::from(wal)
+}
+
+fn immutable_uri(path: &Path) -> String {
+    let mut uri = String::from("file:");
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '?' | '#' | '%' => uri.push_str(&format!("%{:02X}", c as u32)),
+            c => uri.push(c),
+        }
+    }
+    uri.push_str("?immutable=1");
+    uri
+}
@@ -0,0 +1,112 @@
+/// A run of printable text found in a binary file.
+pub struct Printable {
+    /// Offset of its first byte in the file.
+    pub offset: usize,
+    pub text: String,
+    /// Found as UTF-16LE, each character takes two bytes in the file.
+    pub wide: bool,
+}
+
+fn printable(c: char) -> bool {
+    c == '\t' || !c.is_control()
+}
+
+/// Pulls out the runs of printable UTF-8 at least `min_len` bytes long, and with `utf16` the
+/// runs of at least `min_len` printable ASCII characters stored as UTF-16LE, like `strings -e l`
+/// finds in Windows binaries. They come back sorted by offset.
+pub fn find_strings(bytes: &[u8], min_len: usize, utf16: bool) -> Vec<Printable> {
+    let mut found = utf8_runs(bytes, min_len);
+    if utf16 {
+        // a run can start at an odd offset as well
+        found.extend(utf16_runs(bytes, 0, min_len));
+        found.extend(utf16_runs(bytes, 1, min_len));
+        found.sort_by_key(|p| p.offset);
+    }
+    found
+}
+
+fn utf8_runs(bytes: &[u8], min_len: usize) -> Vec<Printable> {
+    let mut found = Vec::new();
+    let mut run = String::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    let mut flush = |run: &mut String, start: usize| {
+        if run.len() >= min_len {
+            found.push(Printable {
+                offset: start,
+                text: std::mem::take(run),
+                wide: false,
+            });
+        }
+        run.clear();
+    };
+
+    while i < bytes.len() {
+        let len = match bytes[i] {
+            0x00..=0x7f => 1,
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => 0,
+        };
+        let c = bytes
+            .get(i..i + len.max(1))
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .and_then(|s| s.chars().next())
+            .filter(|&c| len > 0 && printable(c));
+
+        match c {
+            Some(c) => {
+                if run.is_empty() {
+                    start = i;
+                }
+                run.push(c);
+                i += len;
+            }
+            None => {
+                flush(&mut run, start);
+                i += 1;
+            }
+        }
+    }
+    flush(&mut run, start);
+
+    found
+}
+
+fn utf16_runs(bytes: &[u8], parity: usize, min_len: usize) -> Vec<Printable> {
+    let mut found = Vec::new();
+    let mut run = String::new();
+    let mut start = parity;
+
+    for (k, unit) in bytes[parity.min(bytes.len())..].chunks_exact(2).enumerate() {
+        let offset = parity + 2 * k;
+        let c = char::from(unit[0]);
+        if unit[1] == 0 && c.is_ascii() && printable(c) {
+            if run.is_empty() {
+                start = offset;
+            }
+            run.push(c);
+            continue;
+        }
+
+        if run.len() >= min_len {
+            found.push(Printable {
+                offset: start,
+                text: std::mem::take(&mut run),
+                wide: true,
+            });
+        }
+        run.clear();
+    }
+    if run.len() >= min_len {
+        found.push(Printable {
+            offset: start,
+            text: run,
+            wide: true,
+        });
+    }
+
+    found
+}
@@ -1,13 +1,31 @@
-use regex::Regex;
-
+#[cfg(feature = "sqlite")]
+use super::sqlite::{for_each_text, is_database};
+use super::{
+    archive::{Archive, for_each_member, for_each_tar_member},
+    csv::{delimiter, search_csv},
+    decode::{SourceOffsets, decode},
+    decompress::{Compression, decompress, peek, reader},
+    hexdump::dump_match,
+    lines::split_chunks,
+    near::search_near,
+    preprocess::preprocess,
+    strings::find_strings,
+};
 use crate::{
     errors::custom_errors::AppError,
-    schemas::files::{FileResult, MatchResult, SearchResult},
+    extract::{DataFormat, Document, Format, extract, select_paths},
+    matcher::{Matcher, Scope},
+    output::printer::print_warning,
+    schemas::{
+        files::{FileResult, MatchResult, SearchResult},
+        options::{BinaryMode, SearchOptions, StringsOptions},
+    },
 };
 use std::{
+    borrow::Cow,
     fs::File,
-    io::{BufRead, BufReader, Read},
-    path::PathBuf,
+    io::{Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
     sync::{
         Arc, Mutex,
         mpsc::{Receiver, Sender},
@@ -16,76 +34,540 @@ use std::{
 
 const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
 
-fn is_binary(file: &mut File) -> bool {
-    let mut buf = [0u8; 8192]; // 8kb
-    if let Ok(n) = file.read(&mut buf) {
-        return buf[..n].contains(&0);
+/// Archives on disk are read as they are expanded, whatever their size. This is the limit of
+/// the ones held in memory: archives nested in another and xz tarballs, which are decompressed
+/// whole. Members of archives have to fit in [`MAX_FILE_SIZE`].
+const MAX_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Documents are read whole too, it is the text extracted from them that must fit in
+/// [`MAX_FILE_SIZE`].
+const MAX_DOCUMENT_SIZE: u64 = 128 * 1024 * 1024;
+
+// the whole file is checked, not just its beginning, otherwise a NUL further down ends up
+// printing garbage lines
+fn is_binary(text: &str) -> bool {
+    text.contains('\0')
+}
+
+/// Sorts the matches of every term by position and drops the ones overlapping an earlier
+/// match, since a line can only be highlighted once at each byte.
+pub(super) fn merge_matches(mut matches: Vec<MatchResult>) -> Vec<MatchResult> {
+    matches.sort_by_key(|m| m.match_range);
+
+    let mut last_end = 0;
+    matches.retain(|m| {
+        let keep = m.match_range.0 >= last_end;
+        if keep {
+            last_end = m.match_range.1;
+        }
+        keep
+    });
+
+    matches
+}
+
+/// Whether `head`, the first block of some content, starts an archive or a compressed tarball.
+fn is_archive(head: &[u8]) -> bool {
+    Archive::detect(head).is_some()
+        || Compression::detect(head).is_some_and(|compression| holds_tar(head, compression))
+}
+
+fn holds_tar(bytes: &[u8], compression: Compression) -> bool {
+    matches!(
+        Archive::detect(&peek(bytes, compression, 512)),
+        Some(Archive::Tar)
+    )
+}
+
+/// Points the offsets of results found in transcoded text (or UTF-8 with invalid sequences
+/// replaced) back to the bytes of the file, so `-b` counts what is on disk.
+fn map_offsets(
+    results: &mut [SearchResult],
+    bytes: &[u8],
+    bom_len: usize,
+    options: &SearchOptions,
+) {
+    let mut offsets = SourceOffsets::new(bytes, options.encoding);
+    for r in results {
+        let line = r.byte_offset - bom_len;
+        let first_match = r.matches.iter().map(|m| m.match_range.0).min();
+        r.byte_offset = offsets.get(line);
+        r.match_offset = first_match.map(|start| offsets.get(line + start));
+    }
+}
+
+/// Runs the patterns over each chunk (line or record) of `text`, `base_offset` being where the
+/// text starts in the file. Returns `None` when a `--files` query is false for the whole text.
+///
+/// With `first_only` the search stops at the first qualifying chunk, for binary notices.
+fn search_text(
+    text: &str,
+    base_offset: usize,
+    patterns: &[Matcher],
+    positive: &[bool],
+    options: &SearchOptions,
+    first_only: bool,
+) -> Option<Vec<SearchResult>> {
+    let mut results = Vec::new();
+    // which terms matched somewhere in the file, for --files
+    let mut seen = vec![false; patterns.len()];
+
+    let chunks = split_chunks(
+        text,
+        options.crlf,
+        options.null_data,
+        options.record_separator.as_ref(),
+    );
+
+    for chunk in chunks {
+        let mut matches = Vec::new();
+        let mut hit = vec![false; patterns.len()];
+
+        for (term, pattern) in patterns.iter().enumerate() {
+            let found = pattern.find_iter(chunk.text);
+            if found.is_empty() {
+                continue;
+            }
+
+            hit[term] = true;
+            seen[term] = true;
+            if positive[term] {
+                matches.extend(found.into_iter().map(|mut m| {
+                    m.term = term;
+                    m
+                }));
+            }
+        }
+
+        // in file scope the expression is only decided at the end of the file, the chunks
+        // shown are the ones with positive terms
+        let qualifies = match options.scope {
+            Scope::Lines => options.query.eval(&|term| hit[term]),
+            Scope::Files => !matches.is_empty(),
+        };
+
+        if qualifies {
+            let mut result = SearchResult::new(
+                chunk.line,
+                base_offset + chunk.offset,
+                chunk.text.to_string(),
+                merge_matches(matches),
+            );
+            result.record = chunk.record;
+            result.terminator = chunk.terminator;
+            results.push(result);
+
+            // one match is enough, unless the whole file must be evaluated
+            if first_only && options.scope == Scope::Lines {
+                break;
+            }
+        }
+    }
+
+    if options.scope == Scope::Files && !options.query.eval(&|term| seen[term]) {
+        return None;
+    }
+
+    Some(results)
+}
+
+/// What a worker needs to search the content of a file, or of a member of an archive.
+struct Searcher<'a> {
+    patterns: &'a [Matcher],
+    positive: &'a [bool],
+    options: &'a SearchOptions,
+    result_tx: &'a Sender<FileResult>,
+}
+
+impl Searcher<'_> {
+    /// Searches `bytes` and sends the results under `path`. `depth` is the number of archives
+    /// the content is nested in, and `extracted` tells that the bytes are not the file's own.
+    fn search(
+        &self,
+        path: PathBuf,
+        mut bytes: Vec<u8>,
+        depth: usize,
+        mut extracted: bool,
+    ) -> Result<(), AppError> {
+        let options = self.options;
+        let expand = depth < options.archive_depth;
+
+        // documents are zip containers too, they are read as such only when asked to
+        let document = options.documents && Format::from_path(&path).is_some();
+
+        if expand && !document && is_archive(&bytes) {
+            return self.expand(&path, Cursor::new(bytes), depth);
+        }
+
+        // the size limit applies to the decompressed content too, a tarball was walked above
+        if let Some(compression) = Compression::detect(&bytes)
+            && options.search_zip
+        {
+            match decompress(&bytes, compression, MAX_FILE_SIZE as usize) {
+                Ok(Some(decompressed)) => {
+                    bytes = decompressed;
+                    extracted = true;
+                }
+                Ok(None) => return Ok(()),
+                // `detect` checks enough of the header that plain content is not taken for a
+                // stream, what fails here is a corrupt or truncated one
+                Err(e) => {
+                    print_warning(&format!("skipping {}: {}", path.display(), e));
+                    return Ok(());
+                }
+            }
+
+            if expand && !document && Archive::detect(&bytes).is_some() {
+                return self.expand(&path, Cursor::new(bytes), depth);
+            }
+        }
+
+        if options.documents
+            && let Some(format) = Format::from_path(&path)
+        {
+            return match extract(format, &bytes, options.notebook_outputs) {
+                Ok(doc) => self.search_document(path, doc),
+                Err(e) => {
+                    print_warning(&format!("skipping {}: {}", path.display(), e));
+                    Ok(())
+                }
+            };
+        }
+
+        if bytes.len() as u64 > MAX_FILE_SIZE {
+            return Ok(());
+        }
+
+        // with a path query only the selected parts of structured files are searched
+        if let Some(query) = &options.path_query {
+            let Some(format) = DataFormat::from_path(&path) else {
+                return Ok(());
+            };
+            let (text, _) = decode(&bytes, options.encoding);
+            return match select_paths(format, &text, query, options.path_keys) {
+                Ok(doc) => self.search_document(path, doc),
+                Err(e) => {
+                    print_warning(&format!("skipping {}: {}", path.display(), e));
+                    Ok(())
+                }
+            };
+        }
+
+        // the same goes for the columns of delimited files
+        if !options.csv_columns.is_empty() {
+            let Some(delimiter) = delimiter(&path) else {
+                return Ok(());
+            };
+            let (text, bom_len) = decode(&bytes, options.encoding);
+            return match search_csv(
+                &text,
+                bom_len,
+                delimiter,
+                self.patterns,
+                self.positive,
+                options,
+            ) {
+                Some(mut results) => {
+                    if let Cow::Owned(_) = text {
+                        map_offsets(&mut results, &bytes, bom_len, options);
+                    }
+                    self.send(path, results, false, extracted)
+                }
+                None => Ok(()),
+            };
+        }
+
+        // byte patterns skip the text model altogether, binary files included
+        if let [Matcher::Hex(pattern)] = self.patterns {
+            let results: Vec<SearchResult> = pattern
+                .find_iter(&bytes)
+                .into_iter()
+                .map(|offset| dump_match(&bytes, offset, pattern.len()))
+                .collect();
+            if results.is_empty() {
+                return Ok(());
+            }
+            return self.send(path, results, false, extracted);
+        }
+
+        // binary detection runs on the decoded text, so UTF-16 files are not mistaken for
+        // binaries because of their NUL bytes
+        let (text, bom_len) = decode(&bytes, options.encoding);
+        // NULs are the record terminators of --null-data, not a sign of binary content
+        let binary = !options.null_data && is_binary(&text) && options.binary != BinaryMode::Text;
+        if binary && let Some(strings) = options.strings {
+            return self.search_strings(path, &bytes, strings);
+        }
+        if binary && options.binary == BinaryMode::Skip {
+            return Ok(());
+        }
+
+        match self.find(&text, bom_len, binary) {
+            Some(mut results) => {
+                if let Cow::Owned(_) = text {
+                    map_offsets(&mut results, &bytes, bom_len, options);
+                }
+                self.send(path, results, binary, extracted)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Searches each member of the archive held by `input`, a zip, a tar or a compressed tar,
+    /// under `path!/member`. Only the members are read into memory, nested archives among them
+    /// are expanded in turn while `depth` allows it.
+    fn expand(
+        &self,
+        path: &Path,
+        mut input: impl Read + Seek,
+        depth: usize,
+    ) -> Result<(), AppError> {
+        // only members that will be expanded in turn get the limit of archives
+        let nested = depth + 1 < self.options.archive_depth;
+        let limit = |head: &[u8]| {
+            if nested && is_archive(head) {
+                MAX_ARCHIVE_SIZE
+            } else {
+                MAX_FILE_SIZE
+            }
+        };
+        let search = |name: &str, member| {
+            let member_path = PathBuf::from(format!("{}!/{}", path.display(), name));
+            self.search(member_path, member, depth + 1, true)
+        };
+
+        let mut head = Vec::new();
+        input.rewind()?;
+        input.by_ref().take(512).read_to_end(&mut head)?;
+        input.rewind()?;
+
+        let walked = match (Archive::detect(&head), Compression::detect(&head)) {
+            (Some(archive), _) => for_each_member(input, archive, limit, search),
+            (None, Some(compression)) => reader(input, compression, MAX_ARCHIVE_SIZE as usize)
+                .map_err(AppError::Io)
+                .and_then(|tar| for_each_tar_member(tar, limit, search)),
+            (None, None) => Ok(()),
+        };
+
+        // a corrupt archive is not worth stopping the worker for, what was read of it
+        // has been searched already
+        match walked {
+            Err(AppError::Io(e)) => {
+                print_warning(&format!("stopped reading {}: {}", path.display(), e));
+                Ok(())
+            }
+            walked => walked,
+        }
+    }
+    /// Searches the text extracted from a document, each result pointing to its location in
+    /// the document instead of a line of the text.
+    fn search_document(&self, path: PathBuf, doc: Document) -> Result<(), AppError> {
+        if doc.text.len() as u64 > MAX_FILE_SIZE {
+            return Ok(());
+        }
+
+        let Some(mut results) = self.find(&doc.text, 0, false) else {
+            return Ok(());
+        };
+        for r in &mut results {
+            r.location = Some(doc.location(r.line));
+        }
+        self.send(path, results, false, true)
+    }
+
+    /// Searches the printable strings of a binary file, each one on a line of its own, the
+    /// results pointing to the hex offset of their string.
+    fn search_strings(
+        &self,
+        path: PathBuf,
+        bytes: &[u8],
+        strings: StringsOptions,
+    ) -> Result<(), AppError> {
+        let found = find_strings(bytes, strings.min_len, strings.utf16);
+        let text = found
+            .iter()
+            .map(|p| p.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let Some(mut results) = self.find(&text, 0, false) else {
+            return Ok(());
+        };
+        for r in &mut results {
+            let printable = &found[r.line - 1];
+            r.location = Some(format!("{:#x}", printable.offset));
+            r.byte_offset = printable.offset;
+            // the match positions count the bytes of the decoded text, a UTF-16 string has
+            // twice as many before the first match in the file
+            if printable.wide {
+                r.byte_offset += r.matches.iter().map(|m| m.match_range.0).min().unwrap_or(0);
+            }
+        }
+        self.send(path, results, false, true)
+    }
+
+    /// Searches the text values of a SQLite database, each result pointing to the table, row
+    /// and column of its value. The database is read from the disk, it is never loaded whole.
+    #[cfg(feature = "sqlite")]
+    fn search_database(&self, path: PathBuf) -> Result<(), AppError> {
+        let mut results = Vec::new();
+        let walked = for_each_text(&path, |table, row, column, text| {
+            if text.len() as u64 > MAX_FILE_SIZE {
+                return;
+            }
+            let Some(found) = self.find(text, 0, false) else {
+                return;
+            };
+
+            let multiline = text.lines().nth(1).is_some();
+            for mut r in found {
+                let location = format!("{}:{}:{}", table, row, column);
+                r.location = Some(if multiline {
+                    format!("{}, line {}", location, r.line)
+                } else {
+                    location
+                });
+                results.push(r);
+            }
+        });
+
+        // what was read before the error is still reported
+        if let Err(e) = walked {
+            print_warning(&format!("stopped reading {}: {}", path.display(), e));
+        }
+        self.send(path, results, false, true)
+    }
+
+    /// Runs the search picked on the command line over `text`, `None` meaning that a `--files`
+    /// query is false for it.
+    fn find(&self, text: &str, base_offset: usize, binary: bool) -> Option<Vec<SearchResult>> {
+        let options = self.options;
+        match options.near {
+            Some(distance) => Some(search_near(
+                text,
+                base_offset,
+                self.patterns,
+                distance,
+                options.crlf,
+            )),
+            None => search_text(
+                text,
+                base_offset,
+                self.patterns,
+                self.positive,
+                options,
+                binary,
+            ),
+        }
+    }
+
+    /// Reports the file when it has results. With `--files` it is reported even without any,
+    /// the query holding for the file is enough (`--files --not -e unsafe`).
+    fn send(
+        &self,
+        path: PathBuf,
+        results: Vec<SearchResult>,
+        binary: bool,
+        extracted: bool,
+    ) -> Result<(), AppError> {
+        if !results.is_empty() || self.options.scope == Scope::Files {
+            let mut file_result = FileResult::new(path, results);
+            file_result.binary = binary;
+            file_result.extracted = extracted;
+            self.result_tx.send(file_result)?
+        }
+        Ok(())
     }
-    false
 }
 
 pub fn process_file(
     rx: Arc<Mutex<Receiver<PathBuf>>>,
-    pattern: Regex,
+    patterns: Vec<Matcher>,
+    options: SearchOptions,
     result_tx: Sender<FileResult>,
     prog_tx: Sender<()>,
 ) -> Result<(), AppError> {
+    let positive = options.query.positive_terms(patterns.len());
+    let searcher = Searcher {
+        patterns: &patterns,
+        positive: &positive,
+        options: &options,
+        result_tx: &result_tx,
+    };
+
     loop {
         let msg = {
             let rx = rx.lock()?;
             rx.recv()
         };
 
-        if let Err(_) = msg {
+        if msg.is_err() {
             break;
         }
 
         let path = msg.unwrap();
 
-        let mut results: Vec<SearchResult> = Vec::new();
-
-        let file = File::open(&path)?;
+        let mut file = File::open(&path)?;
 
         // FIXME: this is retarded, need to think in better handler for progress without repeating
         // :(
         prog_tx.send(())?;
 
-        if (file.metadata()?.len() > MAX_FILE_SIZE) || {
-            let mut temp = File::open(&path)?;
-            is_binary(&mut temp)
-        } {
+        // the preprocessor reads the file itself, only its output has to fit in the limit
+        if let Some(pre) = options.pre.as_ref().filter(|pre| pre.applies_to(&path)) {
+            match preprocess(&pre.command, &path) {
+                Ok(output) if output.len() as u64 <= MAX_FILE_SIZE => {
+                    searcher.search(path, output, 0, true)?
+                }
+                Ok(_) => {}
+                Err(e) => print_warning(&format!("skipping {}: {}", path.display(), e)),
+            }
             continue;
         }
 
-        let mut buff = BufReader::new(file);
-        let mut line_no = 0;
-        let mut bytes = Vec::new();
-
-        loop {
-            bytes.clear();
-
-            let n = buff.read_until(b'\n', &mut bytes)?;
-            if n == 0 {
-                break;
-            }
-
-            line_no += 1;
-
-            let text = String::from_utf8_lossy(&bytes);
-            let mut matches: Vec<MatchResult> = Vec::new();
+        // databases are queried in place, whatever their size
+        #[cfg(feature = "sqlite")]
+        if options.sqlite && is_database(&path) {
+            searcher.search_database(path)?;
+            continue;
+        }
 
-            for m in pattern.find_iter(&text) {
-                matches.push(MatchResult::new(m.start(), m.end(), m.as_str().to_string()));
-            }
+        // the magic bytes of zip, tar and the compressed formats are all in the first block
+        let mut bytes = Vec::new();
+        file.by_ref().take(512).read_to_end(&mut bytes)?;
 
-            if !matches.is_empty() {
-                results.push(SearchResult::new(line_no, text.to_string(), matches));
-            }
+        // documents have a larger limit of their own, what is searched in them is judged on the
+        // size of the extracted text. Archives are expanded straight from the file, the limit
+        // applying to each member. A compressed file counts as an archive when it holds a
+        // tarball, which only takes decompressing its start. Documents come first, a `.docx`
+        // is never expanded as the zip it is.
+        let document = options.documents && Format::from_path(&path).is_some();
+        let archive = !document
+            && options.archive_depth > 0
+            && (Archive::detect(&bytes).is_some()
+                || Compression::detect(&bytes).is_some_and(|compression| {
+                    let head = peek(bytes.as_slice().chain(&mut file), compression, 512);
+                    matches!(Archive::detect(&head), Some(Archive::Tar))
+                }));
+        if archive {
+            searcher.expand(&path, file, 0)?;
+            continue;
         }
 
-        if !results.is_empty() {
-            result_tx.send(FileResult::new(path, results))?
+        file.seek(SeekFrom::Start(bytes.len() as u64))?;
+        let len = file.metadata()?.len();
+        let limit = if document {
+            MAX_DOCUMENT_SIZE
+        } else {
+            MAX_FILE_SIZE
+        };
+        if len > limit {
+            continue;
         }
+
+        file.read_to_end(&mut bytes)?;
+
+        searcher.search(path, bytes, 0, false)?;
     }
     Ok(())
 }